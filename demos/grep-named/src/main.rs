@@ -11,11 +11,12 @@ struct Grep {
     ignore_case: bool,
 }
 
-fn main() {
-    let Grep { pattern, file, ignore_case } = Grep::process();
-    eprint!("Searching for `{}` in {}", pattern, file);
-    if ignore_case {
-        eprint!(", ignoring case");
-    }
-    eprintln!();
+fn main() -> type_cli::Outcome {
+    Grep::run(|Grep { pattern, file, ignore_case }| {
+        eprint!("Searching for `{}` in {}", pattern, file);
+        if ignore_case {
+            eprint!(", ignoring case");
+        }
+        eprintln!();
+    })
 }
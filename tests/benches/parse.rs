@@ -0,0 +1,115 @@
+//!
+//! Parsing speed of derived commands with many arguments.
+//!
+//! Run with `cargo bench -p tests`. To compare the size of the code generated by `#[derive(CLI)]`,
+//! count the bytes of this file once it's expanded:
+//! ```text
+//! cargo +nightly rustc -p tests --bench parse --profile=check -- -Zunpretty=expanded | wc -c
+//! ```
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use type_cli::{Parse, CLI};
+
+/// A command with many positional arguments, named arguments and flags.
+#[derive(type_cli::CLI)]
+#[allow(dead_code)]
+struct Wide {
+    source: String,
+    dest: String,
+    count: u32,
+    size: u64,
+    #[optional]
+    label: Option<String>,
+    #[optional]
+    #[variadic]
+    rest: Vec<String>,
+    #[named(short = "a")]
+    alpha: String,
+    #[named(short = "b")]
+    #[optional]
+    beta: Option<u32>,
+    #[named]
+    #[optional]
+    gamma: Option<String>,
+    #[named]
+    #[optional]
+    delta: Option<u64>,
+    #[named]
+    #[optional]
+    epsilon: Option<String>,
+    #[named]
+    #[optional]
+    zeta: Option<String>,
+    #[flag(short = "v")]
+    verbose: bool,
+    #[flag(short = "q")]
+    quiet: bool,
+    #[flag(short = "f")]
+    force: bool,
+    #[flag]
+    dry_run: bool,
+    #[flag]
+    all: bool,
+    #[flag]
+    recursive: bool,
+}
+
+/// An enum with many subcommands, each with a few arguments.
+macro_rules! many {
+    ($($variant: ident),*) => {
+        #[derive(type_cli::CLI)]
+        #[allow(dead_code)]
+        enum Many {
+            $(
+                $variant {
+                    name: String,
+                    #[optional]
+                    path: Option<String>,
+                    #[named(short = "j")]
+                    #[optional]
+                    jobs: Option<u32>,
+                    #[named]
+                    #[optional]
+                    target: Option<String>,
+                    #[flag(short = "v")]
+                    verbose: bool,
+                    #[flag]
+                    force: bool,
+                },
+            )*
+        }
+    };
+}
+many!(Build, Check, Clean, Doc, New, Init, Add, Remove, Run, Test, Bench, Update, Search, Publish, Install, Uninstall);
+
+fn args(args: &[&str]) -> Vec<String> {
+    std::iter::once("bench").chain(args.iter().copied()).map(String::from).collect()
+}
+
+fn success<T>(res: Result<Parse<T>, type_cli::Error>) -> T {
+    match res {
+        Ok(Parse::Success(val)) => val,
+        _ => panic!("expected the arguments to parse"),
+    }
+}
+
+fn bench(c: &mut Criterion) {
+    let wide = args(&[
+        "-v", "src", "--alpha", "x", "dst", "-b", "4", "12", "--dry-run", "4096", "label", "r1", "--zeta", "z", "r2",
+    ]);
+    c.bench_function("wide", |b| b.iter(|| success(Wide::parse(black_box(wide.clone()).into_iter()))));
+
+    let many = args(&["uninstall", "name", "-j", "8", "path", "--force", "--target", "x86"]);
+    c.bench_function("many", |b| b.iter(|| success(Many::parse(black_box(many.clone()).into_iter()))));
+
+    let help = args(&["install", "--help"]);
+    c.bench_function("help", |b| {
+        b.iter(|| match Many::parse(black_box(help.clone()).into_iter()) {
+            Ok(Parse::Help(help)) => help,
+            _ => panic!("expected help"),
+        })
+    });
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
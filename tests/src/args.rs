@@ -0,0 +1,120 @@
+use std::collections::BTreeMap;
+use type_cli::{ArgRef, Argument, Error};
+
+/// Only implements `FromStr`.
+#[derive(PartialEq, Eq, Debug)]
+pub struct Upper(String);
+impl std::str::FromStr for Upper {
+    type Err = std::convert::Infallible;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Upper(s.to_uppercase()))
+    }
+}
+
+/// Implements `Argument` directly, which takes priority over `FromStr`.
+#[derive(PartialEq, Eq, Debug)]
+pub struct Level(u8);
+impl std::str::FromStr for Level {
+    type Err = std::num::ParseIntError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Level)
+    }
+}
+impl Argument for Level {
+    fn parse(val: impl AsRef<str>, arg: ArgRef) -> Result<Self, Error> {
+        match val.as_ref() {
+            "low" => Ok(Level(1)),
+            "high" => Ok(Level(9)),
+            val => u8::parse(val, arg).map(Level),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, type_cli::CLI)]
+pub struct Paint {
+    color: Upper,
+    #[named]
+    level: Level,
+    #[named]
+    #[optional]
+    size: Option<(u32, u32)>,
+    #[named]
+    #[optional]
+    border: Option<Option<u32>>,
+}
+
+#[derive(PartialEq, Eq, Debug, type_cli::CLI)]
+pub struct Shift(std::num::NonZeroI32, #[optional] Option<Upper>);
+
+#[derive(PartialEq, Eq, Debug, type_cli::CLI)]
+pub struct Env(#[variadic] BTreeMap<String, String>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paint() {
+        assert_eq!(
+            process!(Paint, "red" "--level" "high").unwrap(),
+            Paint {
+                color: Upper("RED".to_string()),
+                level: Level(9),
+                size: None,
+                border: None,
+            }
+        );
+    }
+    #[test]
+    fn paint_level() {
+        assert_eq!(
+            process!(Paint, "red" "--level" "4").unwrap().level,
+            Level(4)
+        );
+    }
+    #[test]
+    #[should_panic(expected = "Error parsing argument `--level`")]
+    fn paint_level_err() {
+        process!(Paint, "red" "--level" "medium").unwrap();
+    }
+    #[test]
+    fn paint_size() {
+        assert_eq!(
+            process!(Paint, "red" "--level" "low" "--size" "4,3").unwrap().size,
+            Some((4, 3))
+        );
+    }
+    #[test]
+    #[should_panic(expected = "Expected 2 comma-separated values, found 1")]
+    fn paint_size_err() {
+        process!(Paint, "red" "--level" "low" "--size" "4").unwrap();
+    }
+    #[test]
+    fn paint_border() {
+        assert_eq!(
+            process!(Paint, "red" "--level" "low" "--border" "2").unwrap().border,
+            Some(Some(2))
+        );
+        assert_eq!(
+            process!(Paint, "red" "--level" "low" "--border" "").unwrap().border,
+            Some(None)
+        );
+    }
+
+    #[test]
+    fn shift() {
+        assert_eq!(
+            process!(Shift, "7" "up").unwrap(),
+            Shift(std::num::NonZeroI32::new(7).unwrap(), Some(Upper("UP".to_string())))
+        );
+        assert!(process!(Shift, "0").unwrap_err().contains("zero"));
+    }
+
+    #[test]
+    fn env() {
+        let mut map = BTreeMap::new();
+        map.insert("foo".to_string(), "1".to_string());
+        map.insert("bar".to_string(), "2,3".to_string());
+        assert_eq!(process!(Env, "foo,1" "bar,2,3").unwrap(), Env(map));
+    }
+}
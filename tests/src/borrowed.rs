@@ -0,0 +1,96 @@
+use std::path::Path;
+
+#[derive(PartialEq, Eq, Debug, type_cli::CLI)]
+pub struct Get<'a> {
+    key: &'a str,
+    #[variadic]
+    fields: Vec<&'a str>,
+    #[named(short = "d")]
+    #[optional]
+    default: Option<&'a str>,
+    #[named]
+    #[optional]
+    limit: Option<u32>,
+    #[flag(short = "r")]
+    raw: bool,
+}
+
+#[derive(PartialEq, Eq, Debug, type_cli::CLI)]
+#[cli(name = "repl")]
+pub enum Repl<'a> {
+    Set { key: &'a str, value: &'a str },
+    Open(&'a Path),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FileSystem;
+    use type_cli::{Parse, ParseBorrowed};
+
+    fn parse<'a, T: ParseBorrowed<'a>>(args: &'a [&'a str]) -> Result<T, String> {
+        match T::parse_borrowed(args).map_err(|e| e.to_string())? {
+            Parse::Success(val) => Ok(val),
+            Parse::Help(h) => panic!("{}", h),
+            Parse::Version(v) => panic!("{}", v),
+        }
+    }
+
+    #[test]
+    fn get() {
+        let args = ["get", "-r", "user", "name", "--default", "anon", "email", "--limit", "5"];
+        let get: Get = parse(&args).unwrap();
+        assert_eq!(
+            get,
+            Get {
+                key: "user",
+                fields: vec!["name", "email"],
+                default: Some("anon"),
+                limit: Some(5),
+                raw: true,
+            }
+        );
+        // The fields point into the arguments instead of copying them.
+        assert!(std::ptr::eq(get.key, args[2]));
+        assert!(std::ptr::eq(get.default.unwrap(), args[5]));
+    }
+    #[test]
+    fn subcommands() {
+        let line = String::from("repl set colour blue");
+        let words: Vec<&str> = line.split(' ').collect();
+        let set: Repl = parse(&words).unwrap();
+        assert_eq!(set, Repl::Set { key: "colour", value: "blue" });
+        assert!(std::ptr::eq(words[2], &line[9..15]));
+
+        let open: Repl = parse(&["repl", "open", "notes.txt"]).unwrap();
+        assert_eq!(open, Repl::Open(Path::new("notes.txt")));
+    }
+    #[test]
+    fn errors() {
+        assert_eq!(
+            parse::<Get>(&["get", "user", "--limit", "lots"]).unwrap_err(),
+            "Error parsing argument `--limit`:\ninvalid digit found in string",
+        );
+        assert!(parse::<Repl>(&["repl", "set", "colour"]).is_err());
+        let errors = Repl::parse_borrowed_all_errors(&["repl", "mv", "a"]).err().unwrap();
+        assert_eq!(errors.len(), 1);
+    }
+    #[test]
+    fn help() {
+        match Repl::parse_borrowed(&["repl", "--help"]) {
+            Ok(Parse::Help(help)) => assert!(help.to_string().starts_with("Help - repl")),
+            _ => panic!("expected help"),
+        }
+    }
+    /// Commands that don't borrow can be parsed either way.
+    #[test]
+    fn owned() {
+        assert_eq!(
+            parse::<FileSystem>(&["type-cli", "save", "foo", "-v"]).unwrap(),
+            FileSystem::Save {
+                name: "foo".to_string(),
+                verbose: true,
+            }
+        );
+    }
+}
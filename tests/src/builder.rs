@@ -0,0 +1,243 @@
+#[cfg(test)]
+mod tests {
+    use crate::FileSystem;
+    use std::ffi::OsString;
+    use type_cli::{Arg, ArgRef, ColorChoice, Command, ErrorKind, ManPage, Matches, Parse, ProcessConfig, CLI};
+
+    /// The same command as `FileSystem`, built at runtime.
+    fn file_system() -> Command {
+        Command::new("file-system")
+            .about("Save or load files.")
+            .subcommand(
+                Command::new("save")
+                    .about("Save a file")
+                    .example("file-system save notes.txt")
+                    .env("FILE_SYSTEM_ROOT", "Where files are saved.")
+                    .arg(Arg::positional("name").help("Name of the destination file"))
+                    .arg(Arg::flag("verbose").short('v').help("Print on success")),
+            )
+            .subcommand(
+                Command::new(String::from("load-file"))
+                    .about("Load a file")
+                    .arg(Arg::positional("file").help("The file to load"))
+                    .arg(Arg::positional("bytes").variadic().help("Some options or something idk"))
+                    .arg(Arg::named("time_out").help("How long to wait before cancelling (ms)")),
+            )
+    }
+    fn args(args: &[&str]) -> Vec<String> {
+        std::iter::once("type-cli").chain(args.iter().copied()).map(String::from).collect()
+    }
+    fn matches(cmd: &Command, a: &[&str]) -> Matches {
+        match cmd.parse(args(a)) {
+            Ok(Parse::Success(matches)) => matches,
+            Ok(Parse::Help(help)) => panic!("{}", help),
+            Ok(Parse::Version(version)) => panic!("{}", version),
+            Err(e) => panic!("{}", e),
+        }
+    }
+    /// What is shown for the result of parsing.
+    fn show<T>(res: Result<Parse<T>, type_cli::Error>) -> String {
+        match res {
+            Ok(Parse::Success(_)) => "success".to_string(),
+            Ok(Parse::Help(help)) => format!("help: {}", help),
+            Ok(Parse::Version(version)) => format!("version: {}", version),
+            Err(e) => format!("error: {}", e),
+        }
+    }
+    /// What a derived command and a built one show for the same arguments.
+    fn outputs(a: &[&str]) -> (String, String) {
+        (show(FileSystem::parse(args(a).into_iter())), show(file_system().parse(args(a))))
+    }
+
+    #[test]
+    fn matches_values() {
+        let cmd = file_system();
+        let m = matches(&cmd, &["load-file", "foo", "7", "--time-out", "8", "255"]);
+        let (name, load) = m.subcommand().unwrap();
+        assert_eq!(name, "load-file");
+        assert_eq!(load.value::<String>("file").unwrap().as_deref(), Some("foo"));
+        assert_eq!(load.values::<u8>("bytes").unwrap(), [7, 255]);
+        assert_eq!(load.value::<u64>("time_out").unwrap(), Some(8));
+        assert_eq!(load.occurrences("bytes"), 2);
+
+        let m = matches(&cmd, &["save", "-v", "foo", "--verbose"]);
+        let save = m.subcommand().unwrap().1;
+        assert_eq!(save.value_os("name").unwrap(), "foo");
+        assert!(save.contains("verbose"));
+        assert_eq!(save.occurrences("verbose"), 2);
+
+        let m = matches(&cmd, &["save", "foo"]);
+        assert!(!m.subcommand().unwrap().1.contains("verbose"));
+    }
+    #[test]
+    fn value_errors() {
+        let cmd = file_system();
+        let m = matches(&cmd, &["load-file", "foo", "7", "lots", "--time-out", "soon"]);
+        let load = m.subcommand().unwrap().1;
+        let e = load.values::<u8>("bytes").unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::Parse);
+        assert_eq!(e.arg(), Some(ArgRef::Positional(3)));
+        let e = load.value::<u64>("time_out").unwrap_err();
+        assert_eq!(e.arg(), Some(ArgRef::Named("--time-out")));
+        assert_eq!(e.token(), Some("soon"));
+    }
+    /// Names built at runtime are only kept once, however many times the command is built.
+    #[test]
+    fn names_kept_once() {
+        let name = |cmd: Command| {
+            let m = matches(&cmd, &["load-file", "foo", "--time-out", "soon"]);
+            match m.subcommand().unwrap().1.value::<u64>("time_out").unwrap_err().arg() {
+                Some(ArgRef::Named(name)) => name,
+                arg => panic!("{:?}", arg),
+            }
+        };
+        let (first, second) = (name(file_system()), name(file_system()));
+        assert_eq!(first, "--time-out");
+        assert!(std::ptr::eq(first, second));
+    }
+    #[test]
+    #[should_panic(expected = "No argument named `timeout`.")]
+    fn unknown_name() {
+        let cmd = file_system();
+        let m = matches(&cmd, &["load-file", "foo", "--time-out", "8"]);
+        m.subcommand().unwrap().1.contains("timeout");
+    }
+    #[test]
+    fn same_as_derive() {
+        let inputs: &[&[&str]] = &[
+            &[],
+            &["--help"],
+            &["save", "-h"],
+            &["save", "--help"],
+            &["save"],
+            &["load-file", "--help"],
+            &["save", "foo"],
+            &["lod-file", "foo"],
+            &["frobnicate"],
+            &["save", "-v"],
+            &["save", "foo", "too-many"],
+            &["load-file", "foo"],
+            &["load-file", "foo", "--time-out"],
+            &["load-file", "foo", "--lime-out"],
+            &["load-file", "foo", "1", "--time-out", "8", "2"],
+        ];
+        for input in inputs {
+            let (derived, built) = outputs(input);
+            assert_eq!(derived, built, "for {:?}", input);
+        }
+    }
+    #[test]
+    fn all_errors() {
+        let derived = FileSystem::parse_all_errors(args(&["load-file", "--lime-out", "foo", "extra"]));
+        let built = file_system().parse_all_errors(args(&["load-file", "--lime-out", "foo"]));
+        let built: Vec<_> = built.err().unwrap().iter().map(|e| e.kind()).collect();
+        assert_eq!(built, [ErrorKind::UnknownFlag, ErrorKind::ExpectedNamed]);
+        // The derived command fails to parse `extra` as a byte, which the built command doesn't parse.
+        let derived: Vec<_> = derived.err().unwrap().iter().map(|e| e.kind()).collect();
+        assert_eq!(derived, [ErrorKind::UnknownFlag, ErrorKind::Parse, ErrorKind::ExpectedNamed]);
+    }
+    #[test]
+    fn version() {
+        let cmd = Command::new("tool").version("1.2.3").arg(Arg::positional("file").optional());
+        match cmd.parse(args(&["-V"])) {
+            Ok(Parse::Version(version)) => assert_eq!(version.to_string(), "tool 1.2.3"),
+            _ => panic!("expected the version"),
+        }
+        let m = matches(&cmd, &[]);
+        assert!(!m.contains("file"));
+        assert_eq!(cmd.help().usage(), "tool [FILE]");
+    }
+    /// Help leaves out the help of each subcommand, like a derived command's, which is only built by `help_tree`.
+    #[test]
+    fn help_tree() {
+        let cmd = file_system();
+        match cmd.parse(args(&["-h"])) {
+            Ok(Parse::Help(help)) => assert!(help.subcommands().is_empty()),
+            _ => panic!("expected help"),
+        }
+        assert!(cmd.help().subcommands().is_empty());
+        assert_eq!(cmd.help().render(80), FileSystem::help().render(80));
+
+        let tree = cmd.help_tree();
+        let names: Vec<_> = tree.subcommands().iter().map(|h| h.name()).collect();
+        assert_eq!(names, ["save", "load-file"]);
+        assert_eq!(ManPage::from_help(tree).render(), ManPage::new::<FileSystem>().render());
+    }
+    /// Help and errors use the value of `--color` that the parser reads, in the subcommand that's run.
+    #[test]
+    fn color_arg() {
+        let cmd = Command::new("tool").subcommand(
+            Command::new("ls")
+                .arg(Arg::positional("dir").optional())
+                .arg(Arg::named("color").optional()),
+        );
+        let color = |a: &[&str]| {
+            let exit = cmd.try_process_from(ProcessConfig::new(), args(a).into_iter().map(OsString::from).collect());
+            exit.expect_err("expected help or an error").color()
+        };
+        assert_eq!(color(&["ls", "--color", "never", "-x"]), ColorChoice::Never);
+        assert_eq!(color(&["--color", "never"]), ColorChoice::Auto);
+        assert_eq!(color(&["ls", "--", "--color", "never"]), ColorChoice::Auto);
+        assert_eq!(color(&["ls", "a", "b", "--color", "never"]), ColorChoice::Auto);
+        // There's no `--` to end the flags.
+        let e = cmd.parse(args(&["ls", "--", "x"])).err().unwrap();
+        assert_eq!(e.kind(), ErrorKind::UnknownFlag);
+    }
+    /// A subcommand's own version is used instead of the version of the command above it.
+    #[test]
+    fn subcommand_version() {
+        let version = |cmd: &Command, a: &[&str]| match cmd.parse(args(a)) {
+            Ok(Parse::Version(version)) => version.version(),
+            res => panic!("expected the version, got {}", show(res)),
+        };
+        let cmd = Command::new("tool").subcommand(Command::new("plug").version("9.9"));
+        assert_eq!(version(&cmd, &["plug", "--version"]), "9.9");
+        assert_eq!(version(&cmd, &["plug", "-V"]), "9.9");
+        assert!(show(cmd.parse(args(&["-V"]))).starts_with("error: Unknown subcommand `-V`"));
+
+        let cmd = Command::new("tool")
+            .version("1.0")
+            .subcommand(Command::new("plug").version("9.9"))
+            .subcommand(Command::new("core"));
+        assert_eq!(version(&cmd, &["-V"]), "1.0");
+        assert_eq!(version(&cmd, &["plug", "-V"]), "9.9");
+        assert_eq!(version(&cmd, &["core", "-V"]), "1.0");
+    }
+    #[test]
+    #[should_panic(expected = "Required positional argument `b` must come before any optional arguments.")]
+    fn required_after_optional() {
+        Command::new("tool")
+            .arg(Arg::positional("a").optional())
+            .arg(Arg::positional("b"));
+    }
+    #[test]
+    #[should_panic(expected = "Argument `help` can't use `-h`, which is already used to show help or the version")]
+    fn builtin_help() {
+        Command::new("tool").arg(Arg::flag("help").short('h'));
+    }
+    #[test]
+    #[should_panic(expected = "Argument `verbose` can't use `-V`, which is already used to show help or the version")]
+    fn builtin_version() {
+        // `-V` is free until the command has a version.
+        let cmd = Command::new("tool").arg(Arg::flag("verbose").short('V'));
+        assert!(matches(&cmd, &["-V"]).contains("verbose"));
+        Command::new("tool").subcommand(cmd).version("1.0.0");
+    }
+    #[test]
+    #[should_panic(expected = "Argument `x` can't use `x`, which is already used by argument `x`")]
+    fn duplicate_name() {
+        Command::new("tool").arg(Arg::named("x")).arg(Arg::named("x"));
+    }
+    #[test]
+    #[should_panic(expected = "Argument `time-out` can't use `--time-out`, which is already used by argument `time_out`")]
+    fn duplicate_long() {
+        Command::new("tool").arg(Arg::named("time_out")).arg(Arg::flag("time-out"));
+    }
+    #[test]
+    #[should_panic(expected = "Argument `force` can't use `-f`, which is already used by argument `file`")]
+    fn duplicate_short() {
+        Command::new("tool")
+            .arg(Arg::named("file").short('f'))
+            .arg(Arg::flag("force").short('f'));
+    }
+}
@@ -0,0 +1,113 @@
+use type_cli::ColorChoice;
+
+#[derive(PartialEq, Eq, Debug, type_cli::CLI)]
+pub struct Tree {
+    #[optional]
+    dir: Option<String>,
+    /// When to use colors.
+    #[named]
+    #[optional]
+    color: Option<ColorChoice>,
+    #[named]
+    #[optional]
+    pattern: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::{OsStr, OsString};
+    use type_cli::{HelpKind, Parse, ProcessConfig, CLI};
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            process!(Tree, "--color" "never" "src").unwrap(),
+            Tree {
+                dir: Some("src".to_string()),
+                color: Some(ColorChoice::Never),
+                pattern: None,
+            }
+        );
+        assert_eq!(
+            process!(Tree, "--color" "sometimes").unwrap_err(),
+            "Error parsing argument `--color`:\nUnknown color choice `sometimes`, expected `auto`, `always` or `never`",
+        );
+        assert_eq!(
+            "always".parse::<ColorChoice>().unwrap(),
+            ColorChoice::Always
+        );
+        assert_eq!(ColorChoice::Auto.to_string(), "auto");
+    }
+    #[test]
+    fn help() {
+        match parse!(Tree, "--help").unwrap() {
+            Parse::Help(help) => {
+                assert_eq!(help.kind(), HelpKind::Long);
+                assert!(help
+                    .render(80)
+                    .contains("When to use colors.\n        [optional] [possible values: auto, always, never]"));
+            }
+            _ => panic!("expected help"),
+        }
+    }
+    /// Errors say `error:` whether or not they're styled, so only the styling changes.
+    #[test]
+    fn error_prefix() {
+        let exit = ProcessConfig::new().handle(parse!(Tree, "--colour" "never")).unwrap_err();
+        let message = "Unknown flag `--colour`, did you mean `--color`?\n\nUsage: tree [OPTIONS] [DIR]";
+        assert_eq!(exit.render(false), format!("tree: error: {}", message));
+        let styled = if cfg!(feature = "color") {
+            format!("tree: \x1b[1;31merror:\x1b[0m {}", message)
+        } else {
+            format!("tree: error: {}", message)
+        };
+        assert_eq!(exit.render(true), styled);
+    }
+    /// Help and errors use the value of `--color` that the parser reads, even if parsing fails.
+    #[test]
+    fn color_arg() {
+        fn color<T: CLI>(args: &[&str]) -> ColorChoice {
+            let args = args.iter().map(OsString::from).collect();
+            type_cli::try_process_from::<T>(ProcessConfig::new(), None, args)
+                .err()
+                .expect("expected help or an error")
+                .color()
+        }
+        assert_eq!(color::<Tree>(&["tree", "--color", "never", "--bogus"]), ColorChoice::Never);
+        assert_eq!(color::<Tree>(&["tree", "--color", "never", "--color", "always", "-h"]), ColorChoice::Always);
+        // The last value is the one that's parsed, so an invalid one is reported instead of used.
+        assert_eq!(color::<Tree>(&["tree", "--color", "never", "--color", "maybe"]), ColorChoice::Auto);
+        assert_eq!(color::<Tree>(&["tree", "--color"]), ColorChoice::Auto);
+        // `--color` can be the value of another argument.
+        assert_eq!(color::<Tree>(&["tree", "--pattern", "--color", "never", "-h"]), ColorChoice::Auto);
+        // Nothing is read after help, or after an error stops the parser.
+        assert_eq!(color::<Tree>(&["tree", "-h", "--color", "never"]), ColorChoice::Auto);
+        assert_eq!(color::<Tree>(&["tree", "--", "--color", "never"]), ColorChoice::Auto);
+        assert_eq!(color::<Tree>(&["tree", "src", "extra", "--color", "never"]), ColorChoice::Auto);
+        // Commands without a `--color` argument don't take one.
+        assert_eq!(color::<crate::misc::Oof>(&["oof", "--color", "never"]), ColorChoice::Auto);
+    }
+    /// `NO_COLOR` beats `CLICOLOR_FORCE`, which beats checking for a terminal.
+    #[test]
+    fn env() {
+        let color = cfg!(feature = "color");
+        let enabled = |choice: ColorChoice, no_color: Option<&str>, force: Option<&str>, terminal| {
+            choice.enabled_in(no_color.map(OsStr::new), force.map(OsStr::new), terminal)
+        };
+        let auto = |no_color, force, terminal| enabled(ColorChoice::Auto, no_color, force, terminal);
+
+        assert!(!auto(Some("1"), Some("1"), true));
+        assert!(!auto(Some("1"), None, true));
+        // An empty `NO_COLOR` doesn't count.
+        assert_eq!(auto(Some(""), Some("1"), false), color);
+        assert_eq!(auto(None, Some("1"), false), color);
+        assert!(!auto(None, Some("0"), false));
+        assert!(!auto(None, Some(""), false));
+        assert!(!auto(None, None, false));
+        assert_eq!(auto(None, None, true), color);
+        // Choosing explicitly ignores the environment.
+        assert!(!enabled(ColorChoice::Never, None, Some("1"), true));
+        assert_eq!(enabled(ColorChoice::Always, Some("1"), None, false), color);
+    }
+}
@@ -0,0 +1,202 @@
+use type_cli::completions::Shell;
+use type_cli::{ArgRef, Argument, Error};
+
+/// Manage packages.
+#[derive(PartialEq, Eq, Debug, type_cli::CLI)]
+#[cli(name = "pkg")]
+#[version]
+pub enum Pkg {
+    /// Install a package.
+    Install {
+        /// The package to install.
+        name: String,
+        /// Which build of the package to install.
+        #[named(short = "p")]
+        #[optional]
+        profile: Option<Profile>,
+        /// Install it again if it's already installed.
+        #[flag(short = "f")]
+        force: bool,
+    },
+    /// Print a completion script.
+    Completions(Shell),
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum Profile {
+    Debug,
+    Release,
+}
+impl Argument for Profile {
+    fn parse(val: impl AsRef<str>, arg: ArgRef) -> Result<Self, Error> {
+        match val.as_ref() {
+            "debug" => Ok(Profile::Debug),
+            "release" => Ok(Profile::Release),
+            val => Err(Error::parse(arg, val, "Expected `debug` or `release`")),
+        }
+    }
+    fn possible_values() -> Option<&'static [&'static str]> {
+        Some(&["debug", "release"])
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, type_cli::CLI)]
+pub enum Git {
+    Checkout {
+        #[complete_with = "branches"]
+        branch: String,
+        #[named(short = "r")]
+        #[optional]
+        #[complete_with = "remotes"]
+        remote: Option<String>,
+        #[flag(short = "f")]
+        force: bool,
+    },
+    Add(#[variadic] Vec<std::path::PathBuf>),
+}
+
+fn branches(current: &str) -> Vec<String> {
+    // Completion functions can rely on what's been typed so far, but they don't have to.
+    assert!(!current.starts_with('-'));
+    vec!["main".to_string(), "master".to_string(), "feature".to_string()]
+}
+fn remotes(_: &str) -> Vec<String> {
+    vec!["origin".to_string(), "upstream".to_string()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use type_cli::completions::{complete, generate};
+    use type_cli::Parse;
+
+    #[test]
+    fn shell_arg() {
+        match parse!(Pkg, "completions" "zsh") {
+            Ok(Parse::Success(cmd)) => assert_eq!(cmd, Pkg::Completions(Shell::Zsh)),
+            _ => panic!("expected success"),
+        }
+        let e = parse!(Pkg, "completions" "powershell").err().unwrap();
+        assert!(e.to_string().contains("Unknown shell `powershell`, expected `bash`, `zsh` or `fish`"));
+    }
+
+    #[test]
+    fn bash() {
+        let script = generate::<Pkg>(Shell::Bash);
+        assert!(script.starts_with("_pkg() {\n"));
+        assert!(script.contains(r#"            "pkg:install") cmd="pkg__install"; pos=0; continue ;;"#));
+        assert!(script.contains(
+            r#"        "pkg__install:-p"|"pkg__install:--profile") COMPREPLY=($(compgen -W "debug release" -- "${cur}")); return 0 ;;"#
+        ));
+        assert!(script.contains(r#"        pkg) opts="install completions -h --help -V --version" ;;"#));
+        assert!(script.contains(r#"        "pkg__completions:0") opts="${opts} bash zsh fish" ;;"#));
+        assert!(script.ends_with("complete -F _pkg -o bashdefault -o default pkg\n"));
+    }
+    #[test]
+    fn zsh() {
+        let script = generate::<Pkg>(Shell::Zsh);
+        assert!(script.starts_with("#compdef pkg\n"));
+        assert!(script.contains("                'install:Install a package.'\n"));
+        assert!(script.contains(
+            r"'(-p --profile)'{-p,--profile}'[Which build of the package to install.]:profile:(debug release)'"
+        ));
+        assert!(script.contains(r"'(-f --force)'{-f,--force}'[Install it again if it'\''s already installed.]'"));
+        assert!(script.contains(r"':The package to install.:_files'"));
+        assert!(script.contains(r"':arg1:(bash zsh fish)'"));
+    }
+    #[test]
+    fn fish() {
+        assert_eq!(
+            generate::<Pkg>(Shell::Fish),
+            r"complete -c pkg -n __fish_use_subcommand -f -a install -d 'Install a package.'
+complete -c pkg -n __fish_use_subcommand -f -a completions -d 'Print a completion script.'
+complete -c pkg -n __fish_use_subcommand -s h -l help -d 'Print help'
+complete -c pkg -n __fish_use_subcommand -s V -l version -d 'Print version'
+complete -c pkg -n '__fish_seen_subcommand_from install' -s p -l profile -x -a 'debug release' -d 'Which build of the package to install.'
+complete -c pkg -n '__fish_seen_subcommand_from install' -s f -l force -d 'Install it again if it\'s already installed.'
+complete -c pkg -n '__fish_seen_subcommand_from install' -s h -l help -d 'Print help'
+complete -c pkg -n '__fish_seen_subcommand_from install' -s V -l version -d 'Print version'
+complete -c pkg -n '__fish_seen_subcommand_from completions' -s h -l help -d 'Print help'
+complete -c pkg -n '__fish_seen_subcommand_from completions' -s V -l version -d 'Print version'
+complete -c pkg -n '__fish_seen_subcommand_from completions' -f -a 'bash zsh fish'
+"
+        );
+    }
+    #[test]
+    fn fish_struct() {
+        // Commands without subcommands don't need a condition.
+        let script = generate::<crate::misc::Oof>(Shell::Fish);
+        assert_eq!(
+            script,
+            "complete -c oof -s c -l count -r\ncomplete -c oof -s h -l help -d 'Print help'\n"
+        );
+    }
+
+    #[test]
+    fn complete_subcommand() {
+        assert_eq!(complete::<Git>(vec![""]), ["checkout", "add"]);
+        assert_eq!(complete::<Git>(vec!["ch"]), ["checkout"]);
+        assert_eq!(complete::<Git>(vec!["-"]), ["-h", "--help"]);
+        assert!(complete::<Git>(Vec::<String>::new()).contains(&"checkout".to_string()));
+    }
+    #[test]
+    fn complete_with() {
+        assert_eq!(complete::<Git>(vec!["checkout", "ma"]), ["main", "master"]);
+        assert_eq!(complete::<Git>(vec!["checkout", "-f", ""]), ["main", "master", "feature"]);
+        // The cursor is on the value of `--remote`, then on the branch again.
+        assert_eq!(complete::<Git>(vec!["checkout", "--remote", ""]), ["origin", "upstream"]);
+        assert_eq!(complete::<Git>(vec!["checkout", "-r", "up"]), ["upstream"]);
+        assert_eq!(complete::<Git>(vec!["checkout", "-r", "origin", "f"]), ["feature"]);
+        // There's nothing more to complete after the branch.
+        assert!(complete::<Git>(vec!["checkout", "main", ""]).is_empty());
+        assert_eq!(
+            complete::<Git>(vec!["checkout", "main", "--"]),
+            ["--remote", "--force", "--help"]
+        );
+    }
+    /// Words before the cursor are read the same way the parser reads them.
+    #[test]
+    fn complete_like_parser() {
+        // The program would show help or the version instead of running.
+        assert!(complete::<Git>(vec!["checkout", "--help", ""]).is_empty());
+        assert!(complete::<Pkg>(vec!["install", "foo", "-V", "--profile", ""]).is_empty());
+        assert!(complete::<Pkg>(vec!["--version", ""]).is_empty());
+        // `Git` has no `--version`, so `-V` is an unknown flag and is skipped.
+        assert_eq!(complete::<Git>(vec!["checkout", "-V", "ma"]), ["main", "master"]);
+        // The subcommand has to come first.
+        assert!(complete::<Git>(vec!["--force", "checkout", ""]).is_empty());
+    }
+    #[test]
+    fn complete_values() {
+        assert_eq!(complete::<Pkg>(vec!["install", "foo", "--profile", "r"]), ["release"]);
+        assert_eq!(complete::<Pkg>(vec!["completions", ""]), ["bash", "zsh", "fish"]);
+        // File names are left to the shell.
+        assert!(complete::<Git>(vec!["add", "src/", ""]).is_empty());
+    }
+    #[test]
+    fn dynamic_scripts() {
+        let bash = generate::<Git>(Shell::Bash);
+        assert!(bash.starts_with(
+            r#"_git_dynamic() {
+    local IFS=$'\n'
+    COMPREPLY=($(TYPE_CLI_COMPLETE=bash "${COMP_WORDS[0]}" -- "${COMP_WORDS[@]:1:COMP_CWORD}"))
+}"#
+        ));
+        assert!(bash.contains(r#"        "git__checkout:-r"|"git__checkout:--remote") _git_dynamic; return 0 ;;"#));
+        assert!(bash.contains(
+            r#"        "git__checkout:0") if [[ "${cur}" != -* ]]; then _git_dynamic; return 0; fi ;;"#
+        ));
+
+        let zsh = generate::<Git>(Shell::Zsh);
+        assert!(zsh.contains(r"'(-r --remote)'{-r,--remote}'[]:remote:_git_dynamic'"));
+        assert!(zsh.contains(r#"    _git_words=("${(@)words[1,CURRENT]}")"#));
+
+        let fish = generate::<Git>(Shell::Fish);
+        assert!(fish.contains(
+            "complete -c git -n '__fish_seen_subcommand_from checkout' -f -a \
+             '(env TYPE_CLI_COMPLETE=fish git -- (commandline -opc)[2..-1] (commandline -ct))'"
+        ));
+        // Pkg doesn't complete anything itself, so it doesn't need the helper function.
+        assert!(!generate::<Pkg>(Shell::Bash).contains("TYPE_CLI_COMPLETE"));
+    }
+}
@@ -0,0 +1,79 @@
+#[derive(PartialEq, Eq, Debug, type_cli::CLI)]
+pub struct Resize(String, u32, #[optional] Option<u32>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FileSystem;
+    use type_cli::{Errors, Parse, CLI};
+
+    fn parse_all<T: CLI>(args: &[&str]) -> Result<T, Errors> {
+        let args = std::iter::once("type-cli").chain(args.iter().copied());
+        match T::parse_all_errors(args)? {
+            Parse::Success(val) => Ok(val),
+            Parse::Help(h) => panic!("{}", h),
+            Parse::Version(v) => panic!("{}", v),
+        }
+    }
+
+    #[test]
+    fn load_file() {
+        let errs = parse_all::<FileSystem>(&["load-file", "foo", "x", "300", "--lime-out"]).unwrap_err();
+        assert_eq!(errs.len(), 4);
+        assert_eq!(
+            errs.to_string(),
+            "Found 4 errors:\n  \
+               - Unknown flag `--lime-out`, did you mean `--time-out`?\n  \
+               - Error parsing positional argument `2`:\n    invalid digit found in string\n  \
+               - Error parsing positional argument `3`:\n    number too large to fit in target type\n  \
+               - Expected an argument named `--time-out`\n\n\
+             Usage: file-system load-file --time-out <TIME_OUT> <FILE> [BYTES]..."
+        );
+    }
+    #[test]
+    fn load_file_one() {
+        let errs = parse_all::<FileSystem>(&["load-file", "foo", "1"]).unwrap_err();
+        assert_eq!(errs.len(), 1);
+        assert_eq!(
+            errs.to_string(),
+            "Expected an argument named `--time-out`\n\n\
+             Usage: file-system load-file --time-out <TIME_OUT> <FILE> [BYTES]..."
+        );
+    }
+    #[test]
+    fn load_file_ok() {
+        assert_eq!(
+            parse_all::<FileSystem>(&["load-file", "foo", "1", "--time-out", "5"]).unwrap(),
+            FileSystem::LoadFile {
+                file: "foo".to_string(),
+                bytes: vec![1],
+                time_out: 5,
+            }
+        );
+    }
+    #[test]
+    #[should_panic(expected = "Help - load-file")]
+    fn load_file_help() {
+        parse_all::<FileSystem>(&["load-file", "x", "--lime-out", "--help"]).unwrap();
+    }
+    #[test]
+    #[should_panic(expected = "Error parsing positional argument `2`")]
+    fn load_file_first() {
+        // Only the first error is reported by `parse`.
+        process!(FileSystem, "load-file" "foo" "x" "300").unwrap();
+    }
+
+    #[test]
+    fn resize() {
+        let errs = parse_all::<Resize>(&["img", "big", "huge", "extra"]).unwrap_err();
+        let errs: Vec<_> = errs.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            errs,
+            [
+                "Error parsing positional argument `2`:\ninvalid digit found in string",
+                "Error parsing positional argument `3`:\ninvalid digit found in string",
+                "Unexpected positional argument `extra`\n\nUsage: resize <ARG1> <ARG2> [ARG3]",
+            ]
+        );
+    }
+}
@@ -0,0 +1,104 @@
+use type_cli::Argument;
+
+#[derive(PartialEq, Eq, Debug, type_cli::CLI)]
+pub struct Query<T: Argument> {
+    value: T,
+    #[variadic]
+    more: Vec<T>,
+    #[named]
+    #[optional]
+    limit: Option<T>,
+}
+
+/// The bounds that each field needs are added automatically.
+#[derive(PartialEq, Eq, Debug, type_cli::CLI)]
+pub struct Pair<A, B, V>(A, #[optional] Option<B>, #[optional] #[variadic] Vec<V>);
+
+#[derive(PartialEq, Eq, Debug, type_cli::CLI)]
+pub struct Count<F> {
+    #[flag(short = "v")]
+    verbose: F,
+}
+
+#[derive(PartialEq, Eq, Debug, type_cli::CLI)]
+#[cli(name = "calc")]
+pub enum Op<T>
+where
+    T: Copy,
+{
+    Add { a: T, b: T },
+    Neg(T),
+}
+
+#[derive(PartialEq, Eq, Debug, type_cli::CLI)]
+pub struct Lookup<'a, T> {
+    key: &'a str,
+    #[named]
+    limit: T,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use type_cli::{ArgKind, Parse, ParseBorrowed, CLI};
+
+    #[test]
+    fn query() {
+        assert_eq!(
+            process!(Query<u32>, "1" "2" "3" "--limit" "4").unwrap(),
+            Query {
+                value: 1,
+                more: vec![2, 3],
+                limit: Some(4),
+            }
+        );
+        assert_eq!(
+            process!(Query<String>, "a").unwrap(),
+            Query {
+                value: "a".to_string(),
+                more: vec![],
+                limit: None,
+            }
+        );
+        assert_eq!(
+            process!(Query<u32>, "a").unwrap_err(),
+            "Error parsing positional argument `1`:\ninvalid digit found in string",
+        );
+    }
+    #[test]
+    fn tuple() {
+        assert_eq!(
+            process!(Pair<String, u8, bool>, "x" "7" "true" "false").unwrap(),
+            Pair("x".to_string(), Some(7), vec![true, false]),
+        );
+    }
+    #[test]
+    fn flags() {
+        assert_eq!(process!(Count<u8>, "-v" "-v" "-v").unwrap(), Count { verbose: 3 });
+        assert_eq!(process!(Count<bool>, "-v").unwrap(), Count { verbose: true });
+    }
+    #[test]
+    fn subcommands() {
+        assert_eq!(process!(Op<i32>, "add" "1" "2").unwrap(), Op::Add { a: 1, b: 2 });
+        assert_eq!(process!(Op<f64>, "neg" "1.5").unwrap(), Op::Neg(1.5));
+        match parse!(Op<i32>, "--help").unwrap() {
+            Parse::Help(help) => assert!(help.to_string().starts_with("Help - calc")),
+            _ => panic!("expected help"),
+        }
+    }
+    #[test]
+    fn spec() {
+        let spec = Query::<u32>::spec();
+        let value = spec.arg("value").unwrap();
+        assert_eq!((value.kind, value.ty), (ArgKind::Positional, "T"));
+        assert_eq!(spec.arg("limit").unwrap().ty, "Option<T>");
+    }
+    #[test]
+    fn borrowed() {
+        let args = ["lookup", "--limit", "10", "users"];
+        match Lookup::<usize>::parse_borrowed(&args).unwrap() {
+            Parse::Success(lookup) => assert_eq!(lookup, Lookup { key: "users", limit: 10 }),
+            _ => panic!("expected success"),
+        }
+    }
+}
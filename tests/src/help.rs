@@ -0,0 +1,390 @@
+#[derive(PartialEq, Eq, Debug, type_cli::CLI)]
+#[help = "Copy files from one place to another, keeping their permissions and timestamps intact"]
+pub struct Copy {
+    #[help = "The file to copy"]
+    from: String,
+    #[help = "Where to put the copy. If this is a directory, the file will be placed inside of it"]
+    to: String,
+    #[named(short = "b")]
+    #[optional]
+    #[help = "How many bytes to copy at once"]
+    buffer_size: Option<usize>,
+    #[flag(short = "v")]
+    #[help = "Print each file as it is copied"]
+    verbose: bool,
+    #[flag]
+    dry_run: bool,
+}
+
+/// Remove a file.
+///
+/// The file is moved to the trash,
+/// unless `--force` is passed.
+#[derive(PartialEq, Eq, Debug, type_cli::CLI)]
+#[example = "rm notes.txt"]
+#[example = "rm --force --jobs 4 build.log"]
+#[env(TRASH_DIR = "Where removed files are moved to.")]
+#[env(RM_CONFIRM = "If this is set, ask before removing each file.")]
+pub struct Rm {
+    /// The file to remove.
+    ///
+    /// This is only shown by `--help`.
+    file: String,
+    /// Delete the file instead of moving it to the trash.
+    #[flag(short = "f")]
+    force: bool,
+    /// The doc comment isn't used if there is a `#[help]` attribute.
+    #[flag]
+    #[help = "Ask before removing the file"]
+    interactive: bool,
+    /// How many files to remove at once.
+    #[named]
+    #[optional]
+    #[long_help = "How many files to remove at once.\n\nDefaults to the number of CPUs."]
+    jobs: Option<usize>,
+}
+
+#[derive(PartialEq, Eq, Debug, type_cli::CLI)]
+pub struct Clear {
+    #[flag(short = "a")]
+    all: bool,
+}
+
+/// How many times to try again, which is 3 if it isn't passed.
+#[derive(PartialEq, Eq, Debug)]
+pub struct Retries(u32);
+impl type_cli::OptionalArg for Retries {
+    type Value = u32;
+    fn some(val: u32) -> Self {
+        Retries(val)
+    }
+    fn default() -> Self {
+        Retries(3)
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, type_cli::CLI)]
+pub struct Fetch {
+    /// The URL to download.
+    url: String,
+    /// How many times to try again if the download fails.
+    #[named]
+    #[optional]
+    retries: Retries,
+    /// Print a completion script for this shell.
+    #[named]
+    #[optional]
+    completions: Option<type_cli::completions::Shell>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use type_cli::{HelpEntry, HelpInfo, HelpKind, HelpRenderer, HelpText, Parse, CLI};
+
+    fn help<T: CLI>(args: &[&str]) -> HelpInfo {
+        let args = std::iter::once("type-cli").chain(args.iter().copied());
+        match T::parse(args.map(String::from)) {
+            Ok(Parse::Help(help)) => help,
+            _ => panic!("expected help"),
+        }
+    }
+
+    #[test]
+    fn copy_wide() {
+        assert_eq!(
+            help::<Copy>(&["-h"]).render(200),
+            "\
+Help - copy
+Usage: copy [OPTIONS] <FROM> <TO>
+
+Copy files from one place to another, keeping their permissions and timestamps intact
+
+ARGUMENTS:
+    from               The file to copy
+    to                 Where to put the copy. If this is a directory, the file will be placed inside of it
+    -b, --buffer-size  How many bytes to copy at once [optional]
+
+FLAGS:
+    -v, --verbose  Print each file as it is copied
+    --dry-run"
+        );
+    }
+    #[test]
+    fn copy_narrow() {
+        assert_eq!(
+            help::<Copy>(&["-h"]).render(50),
+            "\
+Help - copy
+Usage: copy [OPTIONS] <FROM> <TO>
+
+Copy files from one place to another, keeping
+their permissions and timestamps intact
+
+ARGUMENTS:
+    from               The file to copy
+    to                 Where to put the copy. If
+                       this is a directory, the
+                       file will be placed inside
+                       of it
+    -b, --buffer-size  How many bytes to copy at
+                       once [optional]
+
+FLAGS:
+    -v, --verbose  Print each file as it is copied
+    --dry-run"
+        );
+    }
+    #[test]
+    fn copy_long_name() {
+        // There's no room to line up the descriptions after `--buffer-size`,
+        // so its description goes on the next line.
+        assert_eq!(
+            help::<Copy>(&["-h"]).render(40),
+            "\
+Help - copy
+Usage: copy [OPTIONS] <FROM> <TO>
+
+Copy files from one place to another,
+keeping their permissions and timestamps
+intact
+
+ARGUMENTS:
+    from  The file to copy
+    to    Where to put the copy. If this
+          is a directory, the file will
+          be placed inside of it
+    -b, --buffer-size
+          How many bytes to copy at once
+          [optional]
+
+FLAGS:
+    -v, --verbose  Print each file as it
+                   is copied
+    --dry-run"
+        );
+    }
+
+    #[test]
+    fn doc_comments() {
+        assert_eq!(
+            help::<Rm>(&["-h"]).render(80),
+            "\
+Help - rm
+Usage: rm [OPTIONS] <FILE>
+
+Remove a file.
+
+ARGUMENTS:
+    file    The file to remove.
+    --jobs  How many files to remove at once. [optional]
+
+FLAGS:
+    -f, --force    Delete the file instead of moving it to the trash.
+    --interactive  Ask before removing the file"
+        );
+    }
+
+    #[test]
+    fn long_help() {
+        let help = help::<Rm>(&["--help"]);
+        assert_eq!(help.kind(), HelpKind::Long);
+        assert_eq!(
+            help.render(80),
+            "\
+Help - rm
+Usage: rm [OPTIONS] <FILE>
+
+Remove a file.
+
+The file is moved to the trash, unless --force is passed.
+
+ARGUMENTS:
+    file
+        The file to remove.
+
+        This is only shown by --help.
+
+    --jobs
+        How many files to remove at once.
+
+        Defaults to the number of CPUs.
+        [optional]
+
+FLAGS:
+    -f, --force
+        Delete the file instead of moving it to the trash.
+
+    --interactive
+        Ask before removing the file
+
+ENVIRONMENT:
+    TRASH_DIR
+        Where removed files are moved to.
+
+    RM_CONFIRM
+        If this is set, ask before removing each file.
+
+EXAMPLES:
+    rm notes.txt
+    rm --force --jobs 4 build.log"
+        );
+    }
+    /// Long help lists defaults and possible values under the description, and short help leaves them out.
+    #[test]
+    fn defaults_and_values() {
+        assert_eq!(
+            help::<Fetch>(&["--help"]).render(80),
+            "\
+Help - fetch
+Usage: fetch [OPTIONS] <URL>
+
+ARGUMENTS:
+    url
+        The URL to download.
+
+    --retries
+        How many times to try again if the download fails.
+        [optional] [default: Retries(3)]
+
+    --completions
+        Print a completion script for this shell.
+        [optional] [possible values: bash, zsh, fish]"
+        );
+        assert_eq!(
+            help::<Fetch>(&["-h"]).render(80),
+            "\
+Help - fetch
+Usage: fetch [OPTIONS] <URL>
+
+ARGUMENTS:
+    url            The URL to download.
+    --retries      How many times to try again if the download fails. [optional]
+    --completions  Print a completion script for this shell. [optional]"
+        );
+    }
+    #[test]
+    fn short_help() {
+        assert_eq!(help::<Rm>(&["-h"]).kind(), HelpKind::Short);
+        // Help shown because of missing arguments is short.
+        assert_eq!(help::<Rm>(&[]).kind(), HelpKind::Short);
+    }
+
+    #[test]
+    #[cfg(feature = "color")]
+    fn styled() {
+        let help = help::<Rm>(&["-h"]);
+        let styled = help.render_styled(80);
+        assert!(styled.starts_with("\x1b[1mHelp\x1b[0m - rm\n\x1b[1mUsage:\x1b[0m rm [OPTIONS] <FILE>"));
+        assert!(styled.contains("\x1b[1mFLAGS:\x1b[0m\n    \x1b[32m-f, --force\x1b[0m    Delete"));
+
+        // Without the escape codes, styled help is laid out the same as plain help.
+        let mut stripped = String::new();
+        let mut rest = styled.as_str();
+        while let Some(start) = rest.find('\x1b') {
+            stripped.push_str(&rest[..start]);
+            rest = &rest[start + rest[start..].find('m').unwrap() + 1..];
+        }
+        stripped.push_str(rest);
+        assert_eq!(stripped, help.render(80));
+        assert!(!help.render(80).contains('\x1b'));
+    }
+    /// Without the `color` feature, styled help is the same as plain help.
+    #[test]
+    #[cfg(not(feature = "color"))]
+    fn styled() {
+        let help = help::<Rm>(&["-h"]);
+        assert_eq!(help.render_styled(80), help.render(80));
+    }
+
+    #[test]
+    fn help_without_parsing() {
+        let info = Rm::help();
+        assert_eq!(info.name(), "rm");
+        assert_eq!(info.usage(), "rm [OPTIONS] <FILE>");
+        assert_eq!(info.kind(), HelpKind::Long);
+        assert_eq!(info.render(80), help::<Rm>(&["--help"]).render(80));
+
+        let headings: Vec<_> = crate::FileSystem::help()
+            .sections()
+            .iter()
+            .map(|s| s.heading.to_string())
+            .collect();
+        assert_eq!(headings, ["SUBCOMMANDS"]);
+    }
+    #[test]
+    fn edit_help() {
+        let mut help = Rm::help().footer("See `man rm` for more.");
+        help.sections_mut()[1].entries.push(HelpEntry::new(
+            "--yes",
+            HelpText::new("Don't ask", "Don't ask before removing the file"),
+        ));
+        assert_eq!(
+            HelpRenderer::new().width(80).kind(HelpKind::Short).render(&help),
+            "\
+Help - rm
+Usage: rm [OPTIONS] <FILE>
+
+Remove a file.
+
+ARGUMENTS:
+    file    The file to remove.
+    --jobs  How many files to remove at once. [optional]
+
+FLAGS:
+    -f, --force    Delete the file instead of moving it to the trash.
+    --interactive  Ask before removing the file
+    --yes          Don't ask
+
+See `man rm` for more."
+        );
+    }
+    #[test]
+    fn custom_help() {
+        let help = HelpInfo::new("deploy", "deploy <ENV>").about(HelpText::new("Ship it", "Ship it."));
+        assert_eq!(help.render(80), "Help - deploy\nUsage: deploy <ENV>\n\nShip it.");
+    }
+
+    #[test]
+    fn usage() {
+        fn usage<T: CLI>(args: &[&str]) -> String {
+            help::<T>(args).render(80).lines().nth(1).unwrap().to_string()
+        }
+        assert_eq!(usage::<crate::misc::Name>(&["-h"]), "Usage: name <FIRST> [LAST]");
+        assert_eq!(usage::<crate::misc::Oof>(&["-h"]), "Usage: oof [OPTIONS] <OUCH>");
+        assert_eq!(usage::<Clear>(&["-h"]), "Usage: clear [OPTIONS]");
+        assert_eq!(
+            usage::<crate::FileSystem>(&["load-file", "-h"]),
+            "Usage: file-system load-file --time-out <TIME_OUT> <FILE> [BYTES]..."
+        );
+    }
+    #[test]
+    fn usage_tuple() {
+        let args = ["type-cli"].iter().map(|s| s.to_string());
+        let e = crate::fmt::Format::parse(args).err().unwrap();
+        assert_eq!(e.usage(), Some("format <ARG1> [ARG2]..."));
+    }
+    #[test]
+    fn usage_wrap() {
+        assert_eq!(
+            help::<crate::FileSystem>(&["load-file", "-h"]).render(40).lines().nth(2),
+            Some("       <TIME_OUT> <FILE> [BYTES]...")
+        );
+    }
+
+    #[test]
+    fn file_system() {
+        assert_eq!(
+            help::<crate::FileSystem>(&[]).render(80),
+            "\
+Help - file-system
+Usage: file-system <COMMAND>
+
+Save or load files.
+
+SUBCOMMANDS:
+    save       Save a file
+    load-file  Load a file"
+        );
+    }
+}
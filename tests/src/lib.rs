@@ -2,6 +2,8 @@
 #[help = "Save or load files."]
 pub enum FileSystem {
     #[help = "Save a file"]
+    #[example = "file-system save notes.txt"]
+    #[env(FILE_SYSTEM_ROOT = "Where files are saved.")]
     Save {
         #[help = "Name of the destination file"]
         name: String,
@@ -34,23 +36,40 @@ macro_rules! parse {
         <$ty as type_cli::CLI>::parse(args!("type-cli" $($st)*))
     }
 }
+/// Parse the arguments, panicking with the help message if help is shown.
+/// Errors are converted to their message, so they show up in the panic from `unwrap`.
 #[cfg(test)]
 macro_rules! process {
     ($ty: ty, $($st: literal)*) => {
         match parse!($ty, $($st)*) {
             Ok(type_cli::Parse::Success(val)) => Ok(val),
             Ok(type_cli::Parse::Help(h)) => panic!("{}", h),
-            Err(e) => Err(e),
+            Ok(type_cli::Parse::Version(v)) => panic!("{}", v),
+            Err(e) => Err(e.to_string()),
         }
     }
 }
 
-mod fmt;
-mod misc;
+pub mod args;
+pub mod borrowed;
+pub mod builder;
+pub mod color;
+pub mod completions;
+pub mod errors;
+pub mod fmt;
+pub mod generic;
+pub mod help;
+pub mod man;
+pub mod markdown;
+pub mod misc;
+pub mod os;
+pub mod process;
+pub mod spec;
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use type_cli::{ArgRef, ErrorKind};
 
     #[test]
     #[should_panic(expected = "Help - file-system")]
@@ -68,6 +87,33 @@ mod tests {
         process!(FileSystem, "-h").unwrap();
     }
 
+    #[test]
+    fn help_requested() {
+        let requested = |res| match res {
+            Ok(type_cli::Parse::Help(h)) => h.is_requested(),
+            _ => panic!("expected help"),
+        };
+        assert!(requested(parse!(FileSystem, "--help")));
+        assert!(requested(parse!(FileSystem, "save" "-h")));
+        assert!(!requested(parse!(FileSystem,)));
+        assert!(!requested(parse!(FileSystem, "save")));
+    }
+
+    #[test]
+    #[should_panic(expected = "Unknown subcommand `lod-file`, did you mean `load-file`?")]
+    fn unknown_sub() {
+        process!(FileSystem, "lod-file" "foo").unwrap();
+    }
+    #[test]
+    fn unknown_sub2() {
+        let e = parse!(FileSystem, "frobnicate").err().unwrap();
+        assert_eq!(e.kind(), ErrorKind::UnknownSub);
+        assert_eq!(e.to_string(), "Unknown subcommand `frobnicate`\n\nUsage: file-system <COMMAND>");
+        assert_eq!(e.usage(), Some("file-system <COMMAND>"));
+        assert_eq!(e.suggestion(), None);
+        assert!(e.command_path().is_empty());
+    }
+
     #[test]
     fn save() {
         assert_eq!(
@@ -163,11 +209,45 @@ mod tests {
         process!(FileSystem, "load-file" "foo" "--time-out").unwrap();
     }
     #[test]
-    #[should_panic(expected = "Unknown flag `--lime-out`")]
+    #[should_panic(expected = "Unknown flag `--lime-out`, did you mean `--time-out`?")]
     fn load_file_err3() {
         process!(FileSystem, "load-file" "foo" "--lime-out").unwrap();
     }
     #[test]
+    fn load_file_err4() {
+        let e = parse!(FileSystem, "load-file" "foo" "--lime-out").err().unwrap();
+        assert_eq!(e.kind(), ErrorKind::UnknownFlag);
+        assert_eq!(e.token(), Some("--lime-out"));
+        assert_eq!(e.suggestion(), Some("--time-out"));
+        assert_eq!(e.command_path(), ["load-file"]);
+    }
+    #[test]
+    fn load_file_err5() {
+        let e = parse!(FileSystem, "load-file" "foo" "--frobnicate").err().unwrap();
+        assert_eq!(
+            e.to_string(),
+            "Unknown flag `--frobnicate`\n\nUsage: file-system load-file --time-out <TIME_OUT> <FILE> [BYTES]..."
+        );
+        assert_eq!(e.suggestion(), None);
+    }
+    #[test]
+    fn load_file_err6() {
+        let e = parse!(FileSystem, "load-file" "foo" "--time-out" "soon").err().unwrap();
+        assert_eq!(e.kind(), ErrorKind::Parse);
+        assert_eq!(e.arg(), Some(ArgRef::Named("--time-out")));
+        assert_eq!(e.token(), Some("soon"));
+        // Values that fail to parse aren't usage errors.
+        assert_eq!(e.usage(), None);
+        let source = std::error::Error::source(&e).unwrap();
+        assert_eq!(source.to_string(), "invalid digit found in string");
+    }
+    #[test]
+    fn error_send_sync() {
+        fn assert_send_sync<T: Send + Sync + 'static>() {}
+        assert_send_sync::<type_cli::Error>();
+        assert_send_sync::<type_cli::Errors>();
+    }
+    #[test]
     #[should_panic(expected = "Help - load-file")]
     fn load_file_help() {
         process!(FileSystem, "load-file" "--help").unwrap();
@@ -0,0 +1,155 @@
+#[cfg(test)]
+mod tests {
+    use crate::completions::Pkg;
+    use type_cli::{HelpInfo, HelpText, ManPage, Parse, CLI};
+
+    #[test]
+    fn subcommands() {
+        let page = ManPage::new::<crate::completions::Pkg>()
+            .env("PKG_HOME", "Where packages are installed.")
+            .render();
+        assert_eq!(
+            page.split(".SH ENVIRONMENT").next().unwrap(),
+            r"
+.TH PKG 1
+.SH NAME
+pkg \- Manage packages.
+.SH SYNOPSIS
+\fBpkg\fR <COMMAND>
+.SH DESCRIPTION
+Manage packages.
+.SH SUBCOMMANDS
+.SS pkg install
+\fBpkg install\fR [OPTIONS] <NAME>
+.PP
+Install a package.
+.TP
+\fIname\fR
+The package to install.
+.TP
+\fB\-p\fR, \fB\-\-profile\fR
+Which build of the package to install.
+.IP
+[optional] [possible values: debug, release]
+.TP
+\fB\-f\fR, \fB\-\-force\fR
+Install it again if it's already installed.
+.SS pkg completions
+\fBpkg completions\fR <ARG1>
+.PP
+Print a completion script.
+"
+            .trim_start()
+        );
+        assert!(page.contains(
+            r"
+.SH ENVIRONMENT
+.TP
+\fBPKG_HOME\fR
+Where packages are installed.
+.TP
+\fBCOLUMNS\fR
+The width of the terminal, which help is laid out to fit.
+"
+        ));
+        // Color settings are only listed if colors can be used.
+        assert_eq!(page.contains("\\fBNO_COLOR\\fR"), cfg!(feature = "color"));
+    }
+    /// Only man pages build the help of every subcommand, so help shown at runtime doesn't pay for it.
+    #[test]
+    fn help_tree() {
+        let args = ["pkg", "--help"].iter().map(|s| s.to_string());
+        match Pkg::parse(args) {
+            Ok(Parse::Help(help)) => assert!(help.subcommands().is_empty()),
+            _ => panic!("expected help"),
+        }
+        assert!(Pkg::help().subcommands().is_empty());
+        let names: Vec<_> = Pkg::help_tree().subcommands().iter().map(|h| h.name().to_string()).collect();
+        assert_eq!(names, ["install", "completions"]);
+    }
+    #[test]
+    fn options() {
+        let page = ManPage::new::<crate::help::Rm>().render();
+        assert!(page.contains(
+            r"
+.SH DESCRIPTION
+Remove a file.
+.PP
+The file is moved to the trash, unless \-\-force is passed.
+.SH OPTIONS
+.TP
+\fIfile\fR
+The file to remove.
+.IP
+This is only shown by \-\-help.
+.TP
+\fB\-\-jobs\fR
+How many files to remove at once.
+.IP
+Defaults to the number of CPUs.
+.IP
+[optional]
+.TP
+\fB\-f\fR, \fB\-\-force\fR
+Delete the file instead of moving it to the trash.
+"
+        ));
+        assert!(page.contains(
+            r"
+.SH ENVIRONMENT
+.TP
+\fBTRASH_DIR\fR
+Where removed files are moved to.
+.TP
+\fBRM_CONFIRM\fR
+If this is set, ask before removing each file.
+.TP
+\fBCOLUMNS\fR
+"
+        ));
+        assert!(page.ends_with(
+            r"
+.SH EXAMPLES
+.PP
+rm notes.txt
+.PP
+rm \-\-force \-\-jobs 4 build.log
+"
+        ));
+    }
+    /// Variables read by several subcommands are only described once.
+    #[test]
+    fn env() {
+        let help = HelpInfo::new("pkg", "pkg <COMMAND>")
+            .env("PKG_HOME", "Where packages are installed.")
+            .subcommand(HelpInfo::new("install", "pkg install").env("PKG_HOME", "Where to install the package."));
+        let page = ManPage::from_help(help).env("COLUMNS", "Ignored.").render();
+        assert_eq!(page.matches("\\fBPKG_HOME\\fR").count(), 1);
+        assert!(page.contains("Where packages are installed."));
+        assert!(page.contains("\\fBCOLUMNS\\fR\nIgnored.\n"));
+        assert_eq!(page.matches("\\fBCOLUMNS\\fR").count(), 1);
+    }
+    #[test]
+    fn escape() {
+        let help = HelpInfo::new("deploy", "deploy <ENV>").about(HelpText::new(
+            "Ship it",
+            "Ship it.\n\n.not a request\n'nor this\n\\ is a backslash",
+        ));
+        let page = ManPage::from_help(help).section("8").render();
+        assert!(page.starts_with(
+            r".TH DEPLOY 8
+.SH NAME
+deploy \- Ship it
+.SH SYNOPSIS
+\fBdeploy\fR <ENV>
+.SH DESCRIPTION
+Ship it.
+.PP
+\&.not a request
+\&'nor this
+\e is a backslash
+.SH ENVIRONMENT
+"
+        ));
+    }
+}
@@ -0,0 +1,74 @@
+#[cfg(test)]
+mod tests {
+    use type_cli::MarkdownReference;
+
+    #[test]
+    fn subcommands() {
+        let reference = MarkdownReference::new::<crate::completions::Pkg>().render();
+        assert!(reference.starts_with(
+            "
+# pkg
+
+Manage packages.
+
+**Usage:** `pkg <COMMAND>`
+
+| Subcommand | Description |
+| --- | --- |
+| [`install`](#pkg-install) | Install a package. |
+| [`completions`](#pkg-completions) | Print a completion script. |
+"
+            .trim_start()
+        ));
+        assert!(reference.contains(
+            "
+## pkg install
+
+Install a package.
+
+**Usage:** `pkg install [OPTIONS] <NAME>`
+
+| Argument | Type | Required | Default | Description |
+| --- | --- | --- | --- | --- |
+| `<NAME>` | `String` | yes |  | The package to install. |
+| `-p, --profile <PROFILE>` | `Option<Profile>` | no |  | Which build of the package to install.<br><br>Possible values: `debug`, `release`. |
+| `-f, --force` | `bool` | no | `false` | Install it again if it's already installed. |
+| `-h, --help` | `bool` | no |  | Print help |
+| `-V, --version` | `bool` | no |  | Print version |
+"
+        ));
+        assert!(reference.contains("| `<ARG1>` | `Shell` | yes |  | Possible values: `bash`, `zsh`, `fish`. |"));
+        assert!(reference.ends_with("| `-V, --version` | `bool` | no |  | Print version |\n"));
+    }
+    #[test]
+    fn arguments() {
+        let reference = MarkdownReference::new::<crate::help::Rm>().heading_level(2).render();
+        assert_eq!(
+            reference,
+            "
+## rm
+
+Remove a file.
+
+The file is moved to the trash, unless --force is passed.
+
+**Usage:** `rm [OPTIONS] <FILE>`
+
+| Argument | Type | Required | Default | Description |
+| --- | --- | --- | --- | --- |
+| `<FILE>` | `String` | yes |  | The file to remove.<br><br>This is only shown by --help. |
+| `--jobs <JOBS>` | `Option<usize>` | no |  | How many files to remove at once.<br><br>Defaults to the number of CPUs. |
+| `-f, --force` | `bool` | no | `false` | Delete the file instead of moving it to the trash. |
+| `--interactive` | `bool` | no | `false` | Ask before removing the file |
+| `-h, --help` | `bool` | no |  | Print help |
+"
+            .trim_start()
+        );
+    }
+    #[test]
+    fn variadic() {
+        let reference = MarkdownReference::new::<crate::completions::Git>().render();
+        assert!(reference.contains("| `[ARG1]...` | `Vec<std::path::PathBuf>` | no (variadic) |  |  |"));
+        assert!(reference.contains("| `-r, --remote <REMOTE>` | `Option<String>` | no |  |  |"));
+    }
+}
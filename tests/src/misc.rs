@@ -22,9 +22,133 @@ pub struct Ls {
     dir: Option<String>,
 }
 
+#[derive(PartialEq, Eq, Debug, type_cli::CLI)]
+#[version]
+pub struct Touch {
+    file: String,
+    #[flag(short = "v")]
+    verbose: bool,
+}
+
+#[derive(PartialEq, Eq, Debug, type_cli::CLI)]
+#[version = "2.0.0-beta"]
+pub struct Echo(String, #[variadic] Vec<String>);
+
+#[derive(PartialEq, Eq, Debug, type_cli::CLI)]
+#[version]
+pub enum Vcs {
+    Commit {
+        #[named(short = "m")]
+        message: String,
+    },
+}
+
+// Fields can share names with the locals of the generated parser.
+#[derive(PartialEq, Eq, Debug, type_cli::CLI)]
+pub struct Tar {
+    tokens: String,
+    #[named]
+    #[optional]
+    extra: Option<String>,
+}
+
+#[derive(PartialEq, Eq, Debug, type_cli::CLI)]
+#[cli(name = "fsctl")]
+pub struct Mount {
+    device: String,
+    #[flag]
+    read_only: bool,
+}
+
+#[derive(PartialEq, Eq, Debug, type_cli::CLI)]
+#[cli(argv0)]
+pub struct Busybox(String);
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use type_cli::{Parse, CLI};
+
+    fn version<T: type_cli::CLI>(res: Result<Parse<T>, type_cli::Error>) -> String {
+        match res {
+            Ok(Parse::Version(v)) => v.to_string(),
+            _ => panic!("expected a version"),
+        }
+    }
+    #[test]
+    fn touch_version() {
+        // The required argument isn't checked.
+        assert_eq!(version(parse!(Touch, "--version")), "touch 0.1.0");
+        assert_eq!(version(parse!(Touch, "-v" "-V")), "touch 0.1.0");
+        assert_eq!(version(parse!(Touch, "foo" "-V" "--frobnicate")), "touch 0.1.0");
+        assert_eq!(
+            process!(Touch, "foo").unwrap(),
+            Touch {
+                file: "foo".to_string(),
+                verbose: false,
+            }
+        );
+    }
+    #[test]
+    #[should_panic(expected = "Unknown flag `--verson`, did you mean `--version`?")]
+    fn touch_version_typo() {
+        process!(Touch, "foo" "--verson").unwrap();
+    }
+    #[test]
+    fn echo_version() {
+        assert_eq!(version(parse!(Echo, "-V")), "echo 2.0.0-beta");
+        // Tuple structs don't have flags, so `-V` is only the version if it comes first.
+        assert_eq!(
+            process!(Echo, "foo" "-V").unwrap(),
+            Echo("foo".to_string(), vec!["-V".to_string()])
+        );
+    }
+    #[test]
+    fn vcs_version() {
+        assert_eq!(version(parse!(Vcs, "--version")), "vcs 0.1.0");
+        assert_eq!(version(parse!(Vcs, "commit" "-V")), "vcs 0.1.0");
+    }
+    #[test]
+    #[should_panic(expected = "Unknown flag `--version`")]
+    fn no_version() {
+        process!(Oof, "ouch" "--version").unwrap();
+    }
+
+    #[test]
+    fn tar_field_names() {
+        assert_eq!(
+            process!(Tar, "a.tar" "--extra" "b").unwrap(),
+            Tar {
+                tokens: "a.tar".to_string(),
+                extra: Some("b".to_string()),
+            }
+        );
+    }
+    #[test]
+    #[should_panic(expected = "Unexpected positional argument")]
+    fn tar_extra_arg() {
+        process!(Tar, "a.tar" "b.tar").unwrap();
+    }
+
+    #[test]
+    fn program_name() {
+        let help = Mount::help();
+        assert_eq!(help.name(), "fsctl");
+        assert_eq!(help.usage(), "fsctl [OPTIONS] <DEVICE>");
+
+        let e = parse!(Mount, "--read-onyl").err().unwrap();
+        assert_eq!(e.program(), Some("fsctl"));
+        assert_eq!(e.usage(), Some("fsctl [OPTIONS] <DEVICE>"));
+    }
+    #[test]
+    fn program_argv0() {
+        let args = ["/usr/bin/mytool"].iter().map(|s| s.to_string());
+        let e = Busybox::parse(args).err().unwrap();
+        assert_eq!(e.program(), Some("mytool"));
+        assert_eq!(e.usage(), Some("mytool <ARG1>"));
+        // Without an argv[0], the name of the type is used.
+        assert_eq!(Busybox::help().name(), "busybox");
+    }
 
     #[test]
     fn name() {
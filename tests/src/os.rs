@@ -0,0 +1,54 @@
+use std::{ffi::OsString, path::PathBuf};
+
+#[derive(PartialEq, Eq, Debug, type_cli::CLI)]
+pub struct Cp {
+    from: PathBuf,
+    #[variadic]
+    to: Vec<OsString>,
+    #[named]
+    #[optional]
+    label: Option<String>,
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::ffi::OsStringExt;
+    use type_cli::{Parse, CLI};
+
+    fn invalid() -> OsString {
+        OsString::from_vec(vec![b'f', b'o', 0x80, b'o'])
+    }
+    fn parse_os(args: Vec<OsString>) -> Result<Cp, String> {
+        let args = std::iter::once(OsString::from("type-cli")).chain(args);
+        match Cp::parse_os(args).map_err(|e| e.to_string())? {
+            Parse::Success(val) => Ok(val),
+            Parse::Help(h) => panic!("{}", h),
+            Parse::Version(v) => panic!("{}", v),
+        }
+    }
+
+    #[test]
+    fn copy() {
+        assert_eq!(
+            parse_os(vec![invalid(), "bar".into(), invalid()]).unwrap(),
+            Cp {
+                from: invalid().into(),
+                to: vec!["bar".into(), invalid()],
+                label: None,
+            }
+        );
+    }
+    #[test]
+    #[should_panic(expected = "Invalid unicode in argument `--label`: `fo\u{FFFD}o`")]
+    fn copy_err() {
+        parse_os(vec!["foo".into(), "--label".into(), invalid()]).unwrap();
+    }
+    #[test]
+    #[should_panic(expected = "Unknown flag `--fo\u{FFFD}o`")]
+    fn copy_flag_err() {
+        let mut flag = OsString::from("--");
+        flag.push(invalid());
+        parse_os(vec!["foo".into(), flag]).unwrap();
+    }
+}
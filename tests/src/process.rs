@@ -0,0 +1,140 @@
+#[derive(PartialEq, Eq, Debug, type_cli::CLI)]
+#[version = "1.0.0"]
+pub struct Touch {
+    file: String,
+    #[flag(short = "c")]
+    no_create: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsString;
+    use std::process::{ExitCode, Termination};
+    use type_cli::{Error, Exit, Outcome, Parse, ProcessConfig};
+
+    /// The exit code, and whether the message goes to stdout.
+    fn report(config: ProcessConfig, result: Result<Parse<Touch>, Error>) -> (u8, bool) {
+        let exit: Exit = config.handle(result).unwrap_err();
+        (exit.code(), exit.to_stdout())
+    }
+
+    #[test]
+    fn requested_help() {
+        assert_eq!(
+            report(ProcessConfig::new(), parse!(Touch, "--help")),
+            (0, true)
+        );
+        assert_eq!(report(ProcessConfig::new(), parse!(Touch, "-h")), (0, true));
+        assert_eq!(
+            report(ProcessConfig::sysexits(), parse!(Touch, "--help")),
+            (0, true)
+        );
+        let config = ProcessConfig::new().help_exit_code(3).help_to_stdout(false);
+        assert_eq!(report(config, parse!(Touch, "--help")), (3, false));
+    }
+    #[test]
+    fn missing_args() {
+        let exit = ProcessConfig::new().handle(parse!(Touch,)).unwrap_err();
+        assert!(exit.help().is_some());
+        assert_eq!(report(ProcessConfig::new(), parse!(Touch,)), (2, false));
+        assert_eq!(
+            report(ProcessConfig::sysexits(), parse!(Touch,)),
+            (64, false)
+        );
+    }
+    #[test]
+    fn errors() {
+        let exit = ProcessConfig::new()
+            .handle(parse!(Touch, "a" "--bogus"))
+            .unwrap_err();
+        assert!(exit.error().is_some());
+        assert_eq!(
+            report(ProcessConfig::new(), parse!(Touch, "a" "--bogus")),
+            (2, false)
+        );
+        assert_eq!(
+            report(ProcessConfig::sysexits(), parse!(Touch, "a" "--bogus")),
+            (64, false)
+        );
+        assert_eq!(
+            report(
+                ProcessConfig::new().error_exit_code(1),
+                parse!(Touch, "a" "-x")
+            ),
+            (1, false)
+        );
+    }
+    #[test]
+    fn version() {
+        assert_eq!(
+            report(ProcessConfig::sysexits(), parse!(Touch, "--version")),
+            (0, true)
+        );
+    }
+    #[test]
+    fn success() {
+        let touch = ProcessConfig::new().handle(parse!(Touch, "a")).unwrap();
+        assert_eq!(
+            touch,
+            Touch {
+                file: "a".to_string(),
+                no_create: false
+            }
+        );
+    }
+    #[test]
+    fn report_exit() {
+        let exit = ProcessConfig::sysexits()
+            .handle(parse!(Touch,))
+            .unwrap_err();
+        assert_eq!(exit.code(), 64);
+        assert_eq!(exit.report(), ExitCode::from(64));
+
+        let exit = ProcessConfig::new()
+            .handle(parse!(Touch, "--version"))
+            .unwrap_err();
+        assert_eq!(exit.report(), ExitCode::SUCCESS);
+    }
+    #[test]
+    fn outcome() {
+        let exit = ProcessConfig::new()
+            .handle(parse!(Touch, "a" "-x"))
+            .unwrap_err();
+        let outcome: Outcome = exit.into();
+        match &outcome {
+            Outcome::Exit(exit) => assert_eq!(exit.code(), 2),
+            Outcome::Done(()) => panic!("expected an exit"),
+        }
+        assert_eq!(outcome.report(), ExitCode::from(2));
+        assert_eq!(Outcome::Done(()).report(), ExitCode::SUCCESS);
+    }
+    /// `Result<(), Exit>` returned from `main` prints the `Debug` text, so it's the message instead of the struct.
+    #[test]
+    fn debug() {
+        let exit = ProcessConfig::new()
+            .handle(parse!(Touch, "--version"))
+            .unwrap_err();
+        assert_eq!(format!("{:?}", exit), exit.to_string());
+        assert_eq!(format!("{:?}", exit), "touch 1.0.0");
+    }
+    /// A completion script's request, from `completions::COMPLETE_VAR`, takes priority over parsing the arguments.
+    #[test]
+    fn try_process_with() {
+        let process = |complete: Option<&str>, args: &[&str]| {
+            let args = args.iter().map(OsString::from).collect();
+            type_cli::try_process_from::<Touch>(ProcessConfig::sysexits(), complete.map(OsString::from), args)
+        };
+        let exit = process(Some("bash"), &["touch", "--n"]).unwrap_err();
+        assert_eq!(exit.completions(), Some(&["--no-create".to_string()][..]));
+        assert_eq!((exit.code(), exit.to_stdout()), (0, true));
+
+        assert_eq!(
+            process(None, &["touch", "a", "-c"]).unwrap(),
+            Touch {
+                file: "a".to_string(),
+                no_create: true
+            }
+        );
+    }
+}
@@ -0,0 +1,107 @@
+#[cfg(test)]
+mod tests {
+    use crate::completions::{Git, Pkg};
+    use type_cli::{ArgKind, CLI};
+
+    #[test]
+    fn command() {
+        let spec = Pkg::spec();
+        assert_eq!(spec.name, "pkg");
+        assert_eq!(spec.about, "Manage packages.");
+        assert_eq!(spec.usage, "<COMMAND>");
+        assert!(spec.version);
+        assert!(spec.args.is_empty());
+        let names: Vec<_> = spec.subcommands.iter().map(|s| s.name).collect();
+        assert_eq!(names, ["install", "completions"]);
+        assert_eq!(spec.subcommand("install").unwrap().usage, "[OPTIONS] <NAME>");
+        assert!(spec.subcommand("remove").is_none());
+    }
+    #[test]
+    fn args() {
+        let spec = Pkg::spec();
+        let install = spec.subcommand("install").unwrap();
+
+        let name = install.arg("name").unwrap();
+        assert_eq!(name.kind, ArgKind::Positional);
+        assert_eq!((name.long, name.short), (None, None));
+        assert_eq!(name.ty, "String");
+        assert!(name.required && !name.variadic);
+        assert_eq!(name.help, "The package to install.");
+        assert_eq!(name.default, None);
+
+        let profile = install.arg("profile").unwrap();
+        assert_eq!(profile.kind, ArgKind::Named);
+        assert_eq!((profile.long, profile.short), (Some("--profile"), Some("-p")));
+        assert_eq!(profile.ty, "Option<Profile>");
+        assert!(!profile.required);
+        assert_eq!(profile.default, None);
+        assert_eq!(profile.values, Some(&["debug", "release"][..]));
+
+        let force = install.arg("force").unwrap();
+        assert_eq!(force.kind, ArgKind::Flag);
+        assert_eq!(force.names().collect::<Vec<_>>(), ["-f", "--force"]);
+        assert_eq!(force.default.as_deref(), Some("false"));
+        assert!(!force.required);
+
+        // `--help` and `--version` are built in, so they're only listed as options.
+        let options: Vec<_> = install.options().iter().map(|a| a.long.unwrap()).collect();
+        assert_eq!(options, ["--profile", "--force", "--help", "--version"]);
+        assert_eq!(install.args.len(), 3);
+    }
+    #[test]
+    fn tuple_args() {
+        let spec = Git::spec();
+        let add = spec.subcommand("add").unwrap();
+        let paths: Vec<_> = add.positionals().collect();
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].name, "arg1");
+        assert_eq!(paths[0].ty, "Vec<std::path::PathBuf>");
+        assert!(paths[0].variadic);
+        assert!(!spec.version);
+    }
+    #[test]
+    fn json() {
+        let spec = Git::spec();
+        let add = spec.subcommand("add").unwrap();
+        assert_eq!(
+            add.to_json(),
+            r#"{
+  "schema_version": 1,
+  "command": {
+    "name": "add",
+    "about": "",
+    "long_about": "",
+    "usage": "[ARG1]...",
+    "version": false,
+    "args": [
+      {
+        "kind": "positional",
+        "name": "arg1",
+        "long": null,
+        "short": null,
+        "help": "",
+        "long_help": "",
+        "type": "Vec<std::path::PathBuf>",
+        "required": false,
+        "variadic": true,
+        "default": null,
+        "values": null,
+        "dynamic_completion": false
+      }
+    ],
+    "subcommands": []
+  }
+}
+"#
+        );
+        let json = crate::help::Rm::spec().to_json();
+        assert!(json.contains(r#""long_help": "How many files to remove at once.\n\nDefaults to the number of CPUs.","#));
+        assert!(json.contains(r#""default": null,"#));
+        assert!(!json.contains(r#""default": "None""#));
+        let json = Pkg::spec().to_json();
+        assert!(json.contains(r#""values": [
+              "debug",
+              "release"
+            ],"#));
+    }
+}
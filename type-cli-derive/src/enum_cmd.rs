@@ -3,21 +3,29 @@ use std::iter::IntoIterator as IntoIter;
 use super::to_snake;
 use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
-use syn::{self, Attribute, Ident, Variant};
+use syn::{self, Attribute, Generics, Ident, Variant};
 
 pub(super) fn parse(
     cmd_ident: &Ident,
+    generics: &Generics,
     attrs: Vec<Attribute>,
     variants: impl IntoIter<Item = Variant>,
+    version: Option<&TokenStream2>,
     iter_ident: &Ident,
-) -> TokenStream2 {
+    errors_ident: &Ident,
+) -> super::Command {
     let parse_ty = crate_path!(Parse);
-    let help_ty = crate_path!(HelpInfo);
+    let kind_ty = crate_path!(HelpKind);
     let err_ty = crate_path!(Error);
 
-    let mut subc: Vec<String> = Vec::new();
+    let mut subc: Vec<(String, super::Help)> = Vec::new();
+    let mut names: Vec<String> = Vec::new();
+    let mut specs = Vec::new();
+    let mut sub_help = Vec::new();
+    let mut bounds = Vec::new();
 
     let mut _match = quote! {};
+    let info_ty = crate_path!(HelpInfo);
 
     for Variant {
         ident,
@@ -28,49 +36,75 @@ pub(super) fn parse(
     {
         let name = to_snake(&ident);
 
-        let mut helpmsg = name.clone();
-        if let Some(help) = try_help!(attrs.iter()) {
-            helpmsg.push('\t');
-            helpmsg.push_str(&help);
-        }
-        subc.push(helpmsg);
+        let help = try_help!(&attrs).unwrap_or_default();
+        subc.push((name.clone(), help));
+        names.push(name.clone());
 
-        let ctor = super::struct_cmd::parse(ident, attrs, fields, iter_ident);
+        let super::Command {
+            help,
+            spec,
+            body,
+            bounds: variant_bounds,
+            ..
+        } = super::struct_cmd::parse(
+            ident,
+            generics,
+            attrs,
+            fields,
+            true,
+            version,
+            iter_ident,
+            errors_ident,
+        );
+        specs.push(spec);
+        bounds.extend(variant_bounds);
+        sub_help.push(help.clone());
         _match = quote! {
             #_match
             Some(#name) => {
-                #ctor
+                #errors_ident.enter(#name);
+                #[allow(unused_variables)]
+                let help_info = |program: &str| -> #info_ty { #help };
+                #body
             } ,
         };
     }
 
-    let mut helpmsg = format!("Help - {}\n", to_snake(cmd_ident));
-    if let Some(help) = attrs.iter().find(|a| a.path.is_ident("help")) {
-        match super::parse_help(help) {
-            Ok(help) => {
-                helpmsg.push_str(&help);
-                helpmsg.push_str("\n\n");
-            }
-            Err(e) => return e.to_compile_error().into(),
-        }
-    }
-
-    helpmsg.push_str("SUBCOMMANDS:\n");
-    for subc in subc {
-        helpmsg.push_str("    ");
-        helpmsg.push_str(&subc);
-        helpmsg.push('\n');
-    }
+    let about = try_help!(&attrs);
+    let examples = try_attr!(super::parse_examples(&attrs));
+    let env = try_attr!(super::parse_env(&attrs));
+    let sections = [super::HelpSection {
+        heading: "SUBCOMMANDS",
+        entries: subc,
+    }];
+    let usage = "<COMMAND>";
+    let help = super::help_info(quote! { program.to_string() }, usage, about.as_ref(), &sections, &examples, &env);
+    // Only man pages need the help of every subcommand, so it isn't built when help is shown.
+    let help_tree = quote! { #help #(.subcommand(#sub_help))* };
+    let spec = super::spec(quote! { program }, about.as_ref(), usage, version.is_some(), &[], &specs);
 
-    quote! {
+    let sub = super::as_str(quote! { #iter_ident.next().as_ref() });
+    let match_version = super::match_version(version, errors_ident);
+    let body = quote! {
         use #cmd_ident::*;
 
-        const HELP: &str = #helpmsg;
+        const SUBCOMMANDS: &[&str] = &[#(#names),*];
+        #errors_ident.set_usage(#usage);
 
-        match #iter_ident.next().as_deref() {
+        match #sub {
             #_match
-            Some("--help") | Some("-h") | None => return Ok(#parse_ty::Help(#help_ty(HELP))),
-            Some(sub) => return Err(#err_ty::UnknownSub(sub.to_string())),
+            #match_version
+            Some("--help") => return Ok(#parse_ty::Help(help_info(#errors_ident.program()).shown(true, #kind_ty::Long))),
+            Some("-h") => return Ok(#parse_ty::Help(help_info(#errors_ident.program()).shown(true, #kind_ty::Short))),
+            None => return Ok(#parse_ty::Help(help_info(#errors_ident.program()).shown(false, #kind_ty::Short))),
+            Some(sub) => return Err(#errors_ident.fatal(#err_ty::unknown_sub(sub.to_string(), SUBCOMMANDS))),
         }
+    };
+    super::Command {
+        help,
+        help_tree,
+        spec,
+        body,
+        bounds,
     }
 }
@@ -1,5 +1,6 @@
 use proc_macro::TokenStream;
-use quote::{format_ident, quote};
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote, ToTokens};
 use syn::{self, Attribute, Item};
 
 macro_rules! crate_path {
@@ -18,15 +19,19 @@ macro_rules! crate_path {
 }
 
 macro_rules! try_help {
-    ($iter: expr) => {{
-        let mut iter = $iter;
-        if let Some(help) = iter.find(|a| a.path.is_ident("help")) {
-            match $crate::parse_help(help) {
-                Ok(help) => Some(help),
-                Err(e) => return e.to_compile_error().into(),
-            }
-        } else {
-            None
+    ($attrs: expr) => {{
+        match $crate::parse_help($attrs) {
+            Ok(help) => help,
+            Err(e) => return e.to_compile_error().into(),
+        }
+    }};
+}
+
+macro_rules! try_attr {
+    ($parse: expr) => {{
+        match $parse {
+            Ok(attr) => attr,
+            Err(e) => return e.to_compile_error().into(),
         }
     }};
 }
@@ -34,33 +39,84 @@ macro_rules! try_help {
 mod enum_cmd;
 mod struct_cmd;
 
-#[proc_macro_derive(CLI, attributes(help, named, flag, optional, variadic))]
+#[proc_macro_derive(CLI, attributes(cli, help, long_help, example, env, version, named, flag, optional, variadic, complete_with))]
 pub fn cli(item: TokenStream) -> TokenStream {
     let parse_ty = crate_path!(Parse);
-    let err_ty = crate_path!(Error);
+    let errors_ty = crate_path!(Errors);
+    let collector_ty = crate_path!(ErrorCollector);
     let cli_ty = crate_path!(CLI);
 
     let input: Item = syn::parse(item).expect("failed to parse");
 
     let iter_ident = format_ident!("ARGS_ITER");
+    let errors_ident = format_ident!("ARGS_ERRORS");
     let cmd_ident;
+    let generics;
+    let cli_attrs;
 
     let body = match input {
         Item::Enum(item) => {
             cmd_ident = item.ident;
-            enum_cmd::parse(&cmd_ident, item.attrs, item.variants, &iter_ident)
+            generics = item.generics;
+            cli_attrs = try_attr!(parse_cli_attrs(&item.attrs));
+            let version = try_attr!(parse_version(&item.attrs));
+            enum_cmd::parse(
+                &cmd_ident,
+                &generics,
+                item.attrs,
+                item.variants,
+                version.as_ref(),
+                &iter_ident,
+                &errors_ident,
+            )
         }
         Item::Struct(item) => {
             cmd_ident = item.ident.clone();
-            struct_cmd::parse(item.ident, item.attrs, item.fields, &iter_ident)
+            generics = item.generics;
+            cli_attrs = try_attr!(parse_cli_attrs(&item.attrs));
+            let version = try_attr!(parse_version(&item.attrs));
+            struct_cmd::parse(
+                item.ident,
+                &generics,
+                item.attrs,
+                item.fields,
+                false,
+                version.as_ref(),
+                &iter_ident,
+                &errors_ident,
+            )
         }
         _ => panic!("Only allowed on structs and enums."),
     };
 
-    let ret = quote! {
-        impl #cli_ty for #cmd_ident {
-            fn parse(mut #iter_ident : impl std::iter::Iterator<Item=String>) -> Result<#parse_ty<#cmd_ident>, #err_ty> {
-                let _ = #iter_ident.next();
+    let Command {
+        help,
+        help_tree,
+        spec,
+        body,
+        bounds,
+    } = body;
+    let info_ty = crate_path!(HelpInfo);
+    let raw_ty = crate_path!(RawArg);
+    let parse_args_ty = crate_path!(ParseArgs);
+    let krate = crate_path!();
+    let program = cli_attrs.name.unwrap_or_else(|| to_snake(&cmd_ident));
+    let set_program = if cli_attrs.argv0 {
+        quote! { #errors_ident.set_program_from_argv0(argv0.as_ref().map(|a| #raw_ty::as_os_str(a)), #program); }
+    } else {
+        quote! { #errors_ident.set_program(#program); }
+    };
+    let parse_args = |token: TokenStream2, help_info: TokenStream2| {
+        quote! {
+            fn parse_args(
+                #iter_ident : impl ::std::iter::Iterator<Item = #token>,
+                mut #errors_ident : #collector_ty,
+            ) -> Result<#parse_ty<Self>, #errors_ty> {
+                #[allow(unused_variables)]
+                let help_info = |program: &str| -> #info_ty { #help_info };
+                let mut #iter_ident = #iter_ident;
+                let argv0 = #iter_ident.next();
+                #set_program
                 let ret = {
                     #body
                 };
@@ -68,10 +124,522 @@ pub fn cli(item: TokenStream) -> TokenStream {
             }
         }
     };
+
+    // The generic parameters of the type, with the bounds its arguments need.
+    let mut generics = generics;
+    let where_clause = generics.make_where_clause();
+    for bound in bounds {
+        where_clause.predicates.push(syn::parse_quote! { #bound });
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    // Types with a lifetime don't implement `CLI`, so their help is built in place.
+    let help_info = if generics.lifetimes().next().is_none() {
+        quote! { <Self as #cli_ty>::help_for(program) }
+    } else {
+        help.clone()
+    };
+    let ret = match generics.lifetimes().count() {
+        // Commands that don't borrow can be parsed from owned or borrowed arguments, using the same code.
+        0 => {
+            let parse_args = parse_args(quote! { ArgsToken }, help_info);
+            // The parser is generic over the type of the arguments, as well as the type's own parameters.
+            let mut parse_generics = generics.clone();
+            parse_generics.params.insert(0, syn::parse_quote! { 'args });
+            parse_generics.params.push(syn::parse_quote! { ArgsToken: #raw_ty<'args> });
+            let (parse_impl_generics, _, _) = parse_generics.split_for_impl();
+            quote! {
+                impl #impl_generics #cli_ty for #cmd_ident #ty_generics #where_clause {
+                    fn help() -> #info_ty {
+                        <Self as #cli_ty>::help_for(#program)
+                    }
+                    fn help_for(program: &str) -> #info_ty {
+                        #help
+                    }
+                    fn help_tree() -> #info_ty {
+                        let program: &str = #program;
+                        #help_tree
+                    }
+                    fn spec() -> #krate::CommandSpec {
+                        let program: &'static str = #program;
+                        #spec
+                    }
+                }
+                impl #parse_impl_generics #parse_args_ty<ArgsToken> for #cmd_ident #ty_generics #where_clause {
+                    #parse_args
+                }
+            }
+        }
+        // Commands that borrow from their arguments can only be parsed from `&str`s.
+        1 => {
+            let lifetime = &generics.lifetimes().next().unwrap().lifetime;
+            let parse_args = parse_args(quote! { &#lifetime str }, help_info);
+            quote! {
+                impl #impl_generics #parse_args_ty<&#lifetime str> for #cmd_ident #ty_generics #where_clause {
+                    #parse_args
+                }
+            }
+        }
+        _ => syn::Error::new_spanned(&generics, "Commands can have at most one lifetime").to_compile_error(),
+    };
     ret.into()
 }
 
-fn parse_help(help: &Attribute) -> syn::Result<String> {
+///
+/// The code generated for a command.
+struct Command {
+    /// An expression for the command's `HelpInfo`, given the name of the program as `program: &str`.
+    help: TokenStream2,
+    /// Like `help`, but with the `HelpInfo` of each subcommand added, for man pages.
+    help_tree: TokenStream2,
+    /// An expression for the command's `CommandSpec`, given the name of the program as `program`.
+    spec: TokenStream2,
+    /// Code that parses the command, which can call `help_info(program)` to get the command's `HelpInfo`.
+    body: TokenStream2,
+    /// The bounds that the type's generic parameters need for its arguments to be parsed, such as `T: Argument`.
+    bounds: Vec<TokenStream2>,
+}
+/// Convert a compile error into a command.
+impl From<TokenStream2> for Command {
+    fn from(error: TokenStream2) -> Self {
+        Self {
+            help: quote! { unreachable!() },
+            help_tree: quote! { unreachable!() },
+            spec: quote! { unreachable!() },
+            body: error,
+            bounds: Vec::new(),
+        }
+    }
+}
+
+///
+/// A group of entries in a help page, such as `ARGUMENTS` or `FLAGS`.
+struct HelpSection {
+    heading: &'static str,
+    /// The name and description of each entry.
+    entries: Vec<(String, Help)>,
+}
+
+///
+/// Code that builds a `HelpInfo`, which is laid out when it's displayed.
+///
+/// `name` is an expression for the name of the command,
+/// `usage` is the part of the usage line that goes after the name of the program,
+/// `examples` are from the command's `#[example = "..."]` attributes, and `env` is from its `#[env(NAME = "...")]` attributes.
+fn help_info(
+    name: TokenStream2,
+    usage: &str,
+    about: Option<&Help>,
+    sections: &[HelpSection],
+    examples: &[String],
+    env: &[(String, String)],
+) -> TokenStream2 {
+    let info_ty = crate_path!(HelpInfo);
+    let usage_line = crate_path!(usage_line);
+    let section_ty = crate_path!(HelpSection);
+    let entry_ty = crate_path!(HelpEntry);
+
+    let about = about.map(|about| {
+        let about = about.to_tokens();
+        quote! { .about(#about) }
+    });
+    let sections = sections
+        .iter()
+        .filter(|s| !s.entries.is_empty())
+        .map(|HelpSection { heading, entries }| {
+            let entries = entries.iter().map(|(name, help)| {
+                let help = help.to_tokens();
+                quote! { #entry_ty::new(#name, #help) }
+            });
+            quote! {
+                .section(#section_ty::new(#heading, vec![#(#entries),*]))
+            }
+        });
+    let env = env.iter().map(|(name, description)| quote! { .env(#name, #description) });
+    quote! {
+        #info_ty::new(#name, #usage_line(program, #usage)) #about #(#sections)* #(.example(#examples))* #(#env)*
+    }
+}
+
+///
+/// Code that builds a `CommandSpec`, which describes a command to shell completions and references.
+///
+/// `name` is an expression for the name of the command, `usage` is the arguments in its usage line,
+/// and `args` and `subcommands` are expressions for each of its `ArgSpec`s and `CommandSpec`s.
+fn spec(
+    name: TokenStream2,
+    about: Option<&Help>,
+    usage: &str,
+    version: bool,
+    args: &[TokenStream2],
+    subcommands: &[TokenStream2],
+) -> TokenStream2 {
+    let krate = crate_path!();
+    let (about, long_about) = about.map_or(("", ""), |about| (&about.short, &about.long));
+    quote! {
+        #krate::CommandSpec {
+            name: #name,
+            about: #about,
+            long_about: #long_about,
+            usage: #usage,
+            version: #version,
+            args: vec![#(#args),*],
+            subcommands: vec![#(#subcommands),*],
+        }
+    }
+}
+
+///
+/// The names of the type parameters of a command, such as `T` in `struct Query<T: Argument>`.
+fn type_params(generics: &syn::Generics) -> Vec<syn::Ident> {
+    generics.type_params().map(|p| p.ident.clone()).collect()
+}
+
+///
+/// The bounds needed to parse an argument of type `ty`, if it uses any of the type parameters `params`.
+///
+/// The value of the argument must implement `Argument`; for optional and variadic arguments,
+/// that's the value inside the `Option` or the items of the collection.
+/// Flags must implement `Flag`.
+fn arg_bounds(ty: &syn::Type, params: &[syn::Ident], required: bool, variadic: bool, flag: bool) -> Vec<TokenStream2> {
+    /// Whether `tokens` contain any of the `params`, such as `T` in `Vec<T>`.
+    fn mentions(tokens: TokenStream2, params: &[syn::Ident]) -> bool {
+        tokens.into_iter().any(|t| match t {
+            proc_macro2::TokenTree::Ident(ident) => params.contains(&ident),
+            proc_macro2::TokenTree::Group(group) => mentions(group.stream(), params),
+            _ => false,
+        })
+    }
+    if !mentions(ty.to_token_stream(), params) {
+        return Vec::new();
+    }
+    let arg_ty = crate_path!(Argument);
+    if flag {
+        let flag_ty = crate_path!(Flag);
+        vec![quote! { #ty: #flag_ty }]
+    } else if variadic {
+        let var_ty = crate_path!(VariadicArg);
+        vec![
+            quote! { #ty: #var_ty + ::std::iter::FromIterator<<#ty as #var_ty>::Item> },
+            quote! { <#ty as #var_ty>::Item: #arg_ty },
+        ]
+    } else if !required {
+        let opt_ty = crate_path!(OptionalArg);
+        vec![
+            quote! { #ty: #opt_ty },
+            quote! { <#ty as #opt_ty>::Value: #arg_ty },
+        ]
+    } else {
+        vec![quote! { #ty: #arg_ty }]
+    }
+}
+
+///
+/// A type as it's written in the source, such as `Option<PathBuf>`.
+fn type_name(ty: &syn::Type) -> String {
+    quote!(#ty)
+        .to_string()
+        .replace(" <", "<")
+        .replace("< ", "<")
+        .replace(" >", ">")
+        .replace(" :: ", "::")
+        .replace(" ,", ",")
+        .replace("& ", "&")
+}
+
+///
+/// Code for the default value of an argument of type `ty`, as an `Option<String>`.
+/// `default` is an expression for the value, which is shown with `Debug` if the type implements it.
+fn default_text(ty: impl ToTokens, default: TokenStream2) -> TokenStream2 {
+    let krate = crate_path!();
+    quote! {{
+        #[allow(unused_imports)]
+        use #krate::ViaDebug as _;
+        #[allow(unused_imports)]
+        use #krate::ViaNoDebug as _;
+        (&&#krate::DefaultText::<#ty>::new()).describe(|| #default)
+    }}
+}
+
+///
+/// Code for the default of an `#[optional]` argument of type `ty`, as an `Option<String>`.
+/// There's no default if the type doesn't hold a value when the argument isn't passed, like `Option<T>`.
+fn optional_default(ty: impl ToTokens) -> TokenStream2 {
+    let opt_ty = crate_path!(OptionalArg);
+    let text = default_text(&ty, quote! { default });
+    quote! {{
+        let default = <#ty as #opt_ty>::default();
+        if #opt_ty::has_value(&default) {
+            #text
+        } else {
+            None
+        }
+    }}
+}
+
+///
+/// Help text for a command or argument.
+#[derive(Clone, Default)]
+struct Help {
+    /// A summary, shown by `-h`.
+    short: String,
+    /// The full description, shown by `--help`.
+    long: String,
+    /// Notes such as `[optional]`, shown by `-h` and `--help`.
+    tags: Vec<&'static str>,
+    /// Expressions for notes that are only shown by `--help`, each an `Option<String>`.
+    details: Vec<TokenStream2>,
+}
+impl Help {
+    /// Add `tags` after both descriptions.
+    fn with_tags(mut self, tags: &[&'static str]) -> Self {
+        self.tags.extend(tags);
+        self
+    }
+    /// Add `details` after the long description.
+    fn with_details(mut self, details: Vec<TokenStream2>) -> Self {
+        self.details.extend(details);
+        self
+    }
+    /// Code that builds a `HelpText`.
+    fn to_tokens(&self) -> TokenStream2 {
+        let text_ty = crate_path!(HelpText);
+        let Self { short, long, tags, details } = self;
+        let tags = if tags.is_empty() {
+            None
+        } else {
+            Some(quote! { .tags([#(#tags),*]) })
+        };
+        quote! { #text_ty::new(#short, #long) #tags #(.details(#details))* }
+    }
+}
+
+///
+/// Get the help text from the `#[help = "msg"]` and `#[long_help = "msg"]` attributes,
+/// or from doc comments if there are neither.
+///
+/// The first paragraph of a doc comment or `#[long_help]` is the short help, and the whole thing is the long help.
+/// If there's no long help, the short help is used for both.
+fn parse_help(attrs: &[Attribute]) -> syn::Result<Option<Help>> {
+    let find = |name: &str| {
+        attrs
+            .iter()
+            .find(|a| a.path.is_ident(name))
+            .map(parse_help_attr)
+            .transpose()
+    };
+    let help = find("help")?;
+    let long_help = find("long_help")?;
+    if help.is_some() || long_help.is_some() {
+        let short = help.or_else(|| Some(long_help.as_ref()?.split("\n\n").next()?.to_string()));
+        return Ok(short.map(|short| Help {
+            long: long_help.unwrap_or_else(|| short.clone()),
+            short,
+            ..Help::default()
+        }));
+    }
+    // Each line of a doc comment is its own `#[doc = "..."]` attribute.
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter(|a| a.path.is_ident("doc"))
+        .filter_map(|a| match a.parse_meta() {
+            Ok(syn::Meta::NameValue(syn::MetaNameValue {
+                lit: syn::Lit::Str(line),
+                ..
+            })) => Some(line.value()),
+            _ => None,
+        })
+        .collect();
+    let paragraphs = doc_paragraphs(&lines);
+    Ok(paragraphs.first().map(|short| Help {
+        short: short.clone(),
+        long: paragraphs.join("\n\n"),
+        ..Help::default()
+    }))
+}
+
+///
+/// Get the examples of using a command from its `#[example = "..."]` attributes, in order.
+fn parse_examples(attrs: &[Attribute]) -> syn::Result<Vec<String>> {
+    attrs
+        .iter()
+        .filter(|a| a.path.is_ident("example"))
+        .map(|attr| match attr.parse_meta()? {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                lit: syn::Lit::Str(example),
+                ..
+            }) => Ok(example.value()),
+            _ => Err(syn::Error::new_spanned(
+                attr,
+                r#"Example must be formatted as #[example = "grep -i hello src"]"#,
+            )),
+        })
+        .collect()
+}
+
+///
+/// Get the environment variables that a command reads from its `#[env(NAME = "...")]` attributes, in order.
+fn parse_env(attrs: &[Attribute]) -> syn::Result<Vec<(String, String)>> {
+    let mut env = Vec::new();
+    for attr in attrs.iter().filter(|a| a.path.is_ident("env")) {
+        let err = || syn::Error::new_spanned(attr, r#"Env must be formatted as #[env(GREP_COLOR = "The color of matches.")]"#);
+        let list = match attr.parse_meta()? {
+            syn::Meta::List(list) => list,
+            _ => return Err(err()),
+        };
+        for nested in list.nested {
+            match nested {
+                syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+                    path,
+                    lit: syn::Lit::Str(description),
+                    ..
+                })) => {
+                    let name = path.get_ident().ok_or_else(err)?;
+                    env.push((name.to_string(), description.value()));
+                }
+                _ => return Err(err()),
+            }
+        }
+    }
+    Ok(env)
+}
+
+///
+/// Clean up the lines of a doc comment for display in a terminal.
+///
+/// Lines are split into paragraphs at blank lines, and the lines in each paragraph are joined with spaces.
+/// Code ticks are removed.
+fn doc_paragraphs(lines: &[String]) -> Vec<String> {
+    let mut paragraphs = Vec::new();
+    let mut current = Vec::new();
+    for line in lines.iter().flat_map(|l| l.split('\n')) {
+        let line = line.trim();
+        if line.is_empty() {
+            if !current.is_empty() {
+                paragraphs.push(current.join(" "));
+                current.clear();
+            }
+        } else {
+            current.push(line.replace('`', ""));
+        }
+    }
+    if !current.is_empty() {
+        paragraphs.push(current.join(" "));
+    }
+    paragraphs
+}
+
+///
+/// Options for the whole command, from `#[cli(name = "fsctl")]` or `#[cli(argv0)]`.
+#[derive(Default)]
+struct CliAttrs {
+    /// The name of the program, instead of the name of the type.
+    name: Option<String>,
+    /// Whether to use the file name of `argv[0]` as the name of the program.
+    argv0: bool,
+}
+
+fn parse_cli_attrs(attrs: &[Attribute]) -> syn::Result<CliAttrs> {
+    let mut cli = CliAttrs::default();
+    for attr in attrs.iter().filter(|a| a.path.is_ident("cli")) {
+        let list = match attr.parse_meta()? {
+            syn::Meta::List(list) => list,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    attr.tokens.clone(),
+                    r#"Expected #[cli(name = "...")] or #[cli(argv0)]"#,
+                ))
+            }
+        };
+        for meta in list.nested {
+            match meta {
+                syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+                    path,
+                    lit: syn::Lit::Str(name),
+                    ..
+                })) if path.is_ident("name") => cli.name = Some(name.value()),
+                syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("argv0") => cli.argv0 = true,
+                meta => {
+                    return Err(syn::Error::new_spanned(
+                        meta,
+                        r#"Expected `name = "..."` or `argv0`"#,
+                    ))
+                }
+            }
+        }
+    }
+    Ok(cli)
+}
+
+///
+/// Code for the version string of a command with a `#[version]` or `#[version = "1.2.3"]` attribute.
+///
+/// Without a value, the version is the version of the crate that the command is defined in.
+fn parse_version(attrs: &[Attribute]) -> syn::Result<Option<TokenStream2>> {
+    let attr = match attrs.iter().find(|a| a.path.is_ident("version")) {
+        Some(attr) => attr,
+        None => return Ok(None),
+    };
+    let version = match attr.parse_meta()? {
+        // `env!` is expanded in the user's crate, so this is their version.
+        syn::Meta::Path(_) => quote! { ::std::env!("CARGO_PKG_VERSION") },
+        syn::Meta::NameValue(syn::MetaNameValue {
+            lit: syn::Lit::Str(version),
+            ..
+        }) => quote! { #version },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                attr.tokens.clone(),
+                r#"Version must be formatted as #[version] or #[version = "1.2.3"]"#,
+            ))
+        }
+    };
+    Ok(Some(version))
+}
+
+///
+/// Code to match `--version` or `-V` and return the version, if the command has one.
+fn match_version(version: Option<&TokenStream2>, errors: &syn::Ident) -> TokenStream2 {
+    let parse_ty = crate_path!(Parse);
+    let version_ty = crate_path!(VersionInfo);
+    match version {
+        Some(version) => quote! {
+            Some("--version") | Some("-V") => {
+                return Ok(#parse_ty::Version(#version_ty::new(#errors.program(), #version)))
+            }
+        },
+        None => quote! {},
+    }
+}
+
+///
+/// Get the function from a `#[complete_with = "function"]` attribute, which completes the values of an argument.
+fn parse_complete_with(attrs: &[Attribute]) -> syn::Result<Option<syn::Path>> {
+    let attr = match attrs.iter().find(|a| a.path.is_ident("complete_with")) {
+        Some(attr) => attr,
+        None => return Ok(None),
+    };
+    match attr.parse_meta()? {
+        syn::Meta::NameValue(syn::MetaNameValue {
+            lit: syn::Lit::Str(path),
+            ..
+        }) => path.parse().map(Some),
+        _ => Err(syn::Error::new_spanned(
+            attr.tokens.clone(),
+            r#"Expected #[complete_with = "function"]"#,
+        )),
+    }
+}
+
+///
+/// Code for the function that completes the values of an argument, as an `Option<fn(&str) -> Vec<String>>`.
+fn complete_fn(complete: Option<&syn::Path>) -> TokenStream2 {
+    match complete {
+        Some(path) => quote! { Some(#path as fn(&str) -> Vec<String>) },
+        None => quote! { None },
+    }
+}
+
+fn parse_help_attr(help: &Attribute) -> syn::Result<String> {
     match help.parse_meta()? {
         syn::Meta::NameValue(meta) => {
             if let syn::Lit::Str(help) = meta.lit {
@@ -90,6 +658,76 @@ fn parse_help(help: &Attribute) -> syn::Result<String> {
     }
 }
 
+///
+/// Code to parse a value of type `ty` from an argument, which is an `OsString` or a `&str`.
+/// Uses the type's impl of `BorrowedArgument`, `ArgumentOs` or `Argument` if there is one, otherwise falls back to `FromStr`.
+fn parse_arg(ty: impl ToTokens, val: TokenStream2, argref: TokenStream2) -> TokenStream2 {
+    let parser_ty = crate_path!(ArgParser);
+    let imports = via_imports();
+    quote! {{
+        #imports
+        (&&&&#parser_ty::<#ty>::new()).parse_arg(#val, #argref)
+    }}
+}
+
+///
+/// Code for the possible values of an argument of type `ty`, as an `Option<&'static [&'static str]>`.
+/// They come from the type's impl of `Argument`, if it has one.
+fn possible_values(ty: impl ToTokens) -> TokenStream2 {
+    let parser_ty = crate_path!(ArgParser);
+    let imports = via_imports();
+    quote! {{
+        #imports
+        (&&&&#parser_ty::<#ty>::new()).possible_values()
+    }}
+}
+
+/// Import the traits that choose how an argument is parsed, for `parse_arg` and `possible_values`.
+fn via_imports() -> TokenStream2 {
+    let via_borrowed = crate_path!(ViaBorrowed);
+    let via_os = crate_path!(ViaArgumentOs);
+    let via_arg = crate_path!(ViaArgument);
+    let via_str = crate_path!(ViaFromStr);
+    quote! {
+        #[allow(unused_imports)]
+        use #via_borrowed as _;
+        #[allow(unused_imports)]
+        use #via_os as _;
+        #[allow(unused_imports)]
+        use #via_arg as _;
+        #[allow(unused_imports)]
+        use #via_str as _;
+    }
+}
+
+///
+/// Code to view an `Option<&A>`, where `A` is a `RawArg`, as an `Option<&str>`, for matching against names.
+fn as_str(val: TokenStream2) -> TokenStream2 {
+    let raw_ty = crate_path!(RawArg);
+    quote! { #val.map(|a| #raw_ty::as_os_str(a).to_string_lossy()).as_deref() }
+}
+
+///
+/// Code to show an argument `val`, which is a `RawArg`, in an error message.
+fn lossy(val: TokenStream2) -> TokenStream2 {
+    let raw_ty = crate_path!(RawArg);
+    quote! { #raw_ty::as_os_str(&#val).to_string_lossy() }
+}
+
+///
+/// How a positional argument is shown in a usage line: `<NAME>` if it's required, `[NAME]` if it's optional,
+/// and `[NAME]...` if it's variadic.
+fn usage_arg(name: &str, required: bool, variadic: bool) -> String {
+    let name = name.to_uppercase();
+    if variadic {
+        format!("[{}]...", name)
+    } else if required {
+        format!("<{}>", name)
+    } else {
+        format!("[{}]", name)
+    }
+}
+
 fn to_snake(ident: &impl ToString) -> String {
     let ident = ident.to_string();
     let mut val = String::with_capacity(ident.len());
@@ -1,48 +1,82 @@
 use super::to_snake;
 use proc_macro2::TokenStream as TokenStream2;
 use quote::{format_ident, quote};
-use syn::{self, Attribute, Fields, Ident};
+use syn::{self, Attribute, Fields, Generics, Ident};
 
 mod named;
 mod tuple;
 
+#[allow(clippy::too_many_arguments)]
 pub(super) fn parse(
     cmd_ident: Ident,
+    generics: &Generics,
     attr: Vec<Attribute>,
     fields: Fields,
+    subcommand: bool,
+    version: Option<&TokenStream2>,
     iter_ident: &Ident,
-) -> TokenStream2 {
-    let mut helpmsg = format!("Help - {}\n", to_snake(&cmd_ident));
-    if let Some(help) = try_help!(attr.iter()) {
-        helpmsg.push_str(&help);
-        helpmsg.push_str("\n\n");
-    }
+    errors_ident: &Ident,
+) -> crate::Command {
+    let name = to_snake(&cmd_ident);
+    let about = try_help!(&attr);
+    let examples = try_attr!(crate::parse_examples(&attr));
+    let env = try_attr!(crate::parse_env(&attr));
+    let mut sections = Vec::new();
+    let args_usage;
+    let spec_args;
+    let bounds;
+    let params = crate::type_params(generics);
 
-    let help_ident = format_ident!("HELP");
+    let help_ident = format_ident!("help_info");
 
     let ctor = match fields {
         //
         // Named structs.
         Fields::Named(fields) => {
-            let parser = match named::Parser::collect_args(cmd_ident, fields) {
+            let parser = match named::Parser::collect_args(cmd_ident, fields, version.is_some()) {
                 Ok(parser) => parser,
-                Err(e) => return e.to_compile_error(),
+                Err(e) => return e.to_compile_error().into(),
             };
-            parser.build_help(&mut helpmsg);
-            parser.into_ctor(iter_ident, &help_ident)
+            sections = parser.build_help();
+            args_usage = parser.usage();
+            spec_args = parser.spec_args();
+            bounds = parser.bounds(&params);
+            parser.into_ctor(iter_ident, errors_ident, &help_ident, version)
         }
 
         //
         // Tuple structs.
         Fields::Unnamed(fields) => {
-            let parser = tuple::Parser::collect_args(cmd_ident, fields);
-            parser.into_ctor(iter_ident, &help_ident)
+            let parser = match tuple::Parser::collect_args(cmd_ident, fields) {
+                Ok(parser) => parser,
+                Err(e) => return e.to_compile_error().into(),
+            };
+            args_usage = parser.usage();
+            spec_args = parser.spec_args();
+            bounds = parser.bounds(&params);
+            parser.into_ctor(iter_ident, errors_ident, &help_ident, version)
         }
         Fields::Unit => todo!(),
     };
 
-    quote! {
-        const #help_ident: &str = #helpmsg;
+    // The usage line starts with the name of the program, followed by the subcommand if this is one.
+    let (name, usage) = if subcommand {
+        let usage = std::iter::once(&name).chain(&args_usage);
+        (quote! { #name }, usage.cloned().collect::<Vec<_>>().join(" "))
+    } else {
+        (quote! { program }, args_usage.join(" "))
+    };
+    let help = super::help_info(quote! { #name.to_string() }, &usage, about.as_ref(), &sections, &examples, &env);
+    let spec = super::spec(name, about.as_ref(), &args_usage.join(" "), version.is_some(), &spec_args, &[]);
+    let body = quote! {
+        #errors_ident.set_usage(#usage);
         #ctor
+    };
+    crate::Command {
+        help_tree: help.clone(),
+        help,
+        spec,
+        body,
+        bounds,
     }
 }
@@ -1,4 +1,4 @@
-use crate::to_snake;
+use crate::{to_snake, Help, HelpSection};
 use proc_macro2::TokenStream as TokenStream2;
 use quote::{format_ident, quote};
 use syn::{self, Ident, Type};
@@ -9,16 +9,17 @@ struct Arg {
     arg_name: String,      // The cli-name of the argument. `--arg`
     short: Option<String>, // short name of the argument. `-a`
     name: String,          // The cli-name sans `--`
-    help: Option<String>,
+    help: Option<Help>,
     ty: Type,
     required: bool,
     variadic: bool,
+    complete: Option<syn::Path>, // The function from `#[complete_with = "function"]`.
 }
 impl Arg {
     pub fn new(
         ident: Ident,
         short: Option<String>,
-        help: Option<String>,
+        help: Option<Help>,
         ty: Type,
         required: bool,
         variadic: bool,
@@ -34,8 +35,56 @@ impl Arg {
             ty,
             required,
             variadic,
+            complete: None,
         }
     }
+    /// Code for the possible values of each item in the collection, or of the value in the `Option`.
+    fn values(&self) -> TokenStream2 {
+        let ty = &self.ty;
+        if self.variadic {
+            let var_ty = crate_path!(VariadicArg);
+            crate::possible_values(quote! { <#ty as #var_ty>::Item })
+        } else if !self.required {
+            let opt_ty = crate_path!(OptionalArg);
+            crate::possible_values(quote! { <#ty as #opt_ty>::Value })
+        } else {
+            crate::possible_values(ty)
+        }
+    }
+    /// Code for the default of an argument that takes a value, shown in help, as an `Option<String>`.
+    fn default(&self) -> TokenStream2 {
+        if self.required || self.variadic {
+            quote! { None }
+        } else {
+            crate::optional_default(&self.ty)
+        }
+    }
+    /// Set the function that completes this argument's values.
+    fn complete_with(mut self, complete: Option<syn::Path>) -> Self {
+        self.complete = complete;
+        self
+    }
+}
+
+///
+/// Make sure that a named argument or flag doesn't take the name of `--help` or `--version`,
+/// which would stop the built-in flag from working. `attr` is its `#[named]` or `#[flag]` attribute.
+fn check_builtin(attr: &syn::Attribute, ident: &Ident, short: Option<&str>, version: bool) -> syn::Result<()> {
+    let long = to_snake(ident).replace('_', "-");
+    let taken = match (short, long.as_str()) {
+        (Some("h"), _) => Some("-h"),
+        (_, "help") => Some("--help"),
+        (Some("V"), _) if version => Some("-V"),
+        (_, "version") if version => Some("--version"),
+        _ => None,
+    };
+    match taken {
+        Some(name) => Err(syn::Error::new_spanned(
+            attr,
+            format!("Argument `{}` can't use `{}`, which is already used to show help or the version", ident, name),
+        )),
+        None => Ok(()),
+    }
 }
 
 pub(super) struct Parser {
@@ -47,7 +96,8 @@ pub(super) struct Parser {
 impl Parser {
     ///
     /// Process the fields of the struct into a form relevant to CLI.
-    pub fn collect_args(cmd_ident: Ident, fields: syn::FieldsNamed) -> syn::Result<Self> {
+    /// `version` is whether the command accepts `--version`.
+    pub fn collect_args(cmd_ident: Ident, fields: syn::FieldsNamed, version: bool) -> syn::Result<Self> {
         let short_reg = regex::Regex::new(r#"short\s*=\s*"(.*)""#).unwrap();
 
         let mut pos_args: Vec<Arg> = Vec::new();
@@ -63,46 +113,47 @@ impl Parser {
             let required = !attrs.iter().any(|a| a.path.is_ident("optional"));
             let variadic = attrs.iter().any(|a| a.path.is_ident("variadic"));
 
-            //let help = try_help!(attrs.iter());
-            let help = attrs
-                .iter()
-                .find(|a| a.path.is_ident("help"))
-                .map(crate::parse_help)
-                .transpose()?;
+            let help = crate::parse_help(&attrs)?;
+            let complete = crate::parse_complete_with(&attrs)?;
 
             // Named arguments.
             if let Some(named) = attrs.iter().find(|a| a.path.is_ident("named")) {
                 if variadic {
-                    panic!("Named argument `{}` cannot be variadic.", ident.to_string());
+                    panic!("Named argument `{}` cannot be variadic.", ident);
                 }
                 let short = short_reg
                     .captures(&named.tokens.to_string())
                     .map(|cap| cap[1].to_string());
-                named_args.push(Arg::new(ident, short, help, ty, required, false));
+                check_builtin(named, &ident, short.as_deref(), version)?;
+                named_args.push(Arg::new(ident, short, help, ty, required, false).complete_with(complete));
             }
             // Flags.
             else if let Some(flag) = attrs.iter().find(|a| a.path.is_ident("flag")) {
                 if variadic {
-                    panic!("Flag `{}` cannot be variadic.", ident.to_string());
+                    panic!("Flag `{}` cannot be variadic.", ident);
+                }
+                if complete.is_some() {
+                    return Err(syn::Error::new_spanned(ident, "Flags don't have values to complete"));
                 }
                 let short = short_reg
                     .captures(&flag.tokens.to_string())
                     .map(|cap| cap[1].to_string());
+                check_builtin(flag, &ident, short.as_deref(), version)?;
                 flags.push(Arg::new(ident, short, help, ty, required, false));
             }
             // Positional arguments.
             else {
-                if required && pos_args.last().map_or(false, |a| !a.required) {
-                    panic!("Required positional argument `{}` must come before any optional arguments.", ident.to_string());
+                if required && pos_args.last().is_some_and(|a| !a.required) {
+                    panic!("Required positional argument `{}` must come before any optional arguments.", ident);
                 }
                 if any_variadic {
                     panic!(
                         "Positional argument `{}` must come before the variadic argument.",
-                        ident.to_string()
+                        ident
                     );
                 }
                 any_variadic = any_variadic || variadic;
-                pos_args.push(Arg::new(ident, None, help, ty, required, variadic));
+                pos_args.push(Arg::new(ident, None, help, ty, required, variadic).complete_with(complete));
             }
         }
 
@@ -115,76 +166,177 @@ impl Parser {
     }
     ///
     /// Build help info about this command's arguments.
-    pub fn build_help(&self, helpmsg: &mut String) {
-        // Help info for arguments.
-        let args_empty = self.pos_args.is_empty() && self.named_args.is_empty();
-        if !args_empty {
-            helpmsg.push_str("ARGUMENTS:\n");
+    pub fn build_help(&self) -> Vec<HelpSection> {
+        /// Join an argument's help message with any tags describing it.
+        fn describe(arg: &Arg, tags: &[(bool, &'static str)]) -> Help {
+            let tags: Vec<_> = tags.iter().filter(|t| t.0).map(|t| t.1).collect();
+            arg.help.clone().unwrap_or_default().with_tags(&tags)
         }
-        for arg in &self.pos_args {
-            helpmsg.push_str("    ");
-            helpmsg.push_str(&arg.name);
-            if let Some(help) = &arg.help {
-                helpmsg.push('\t');
-                helpmsg.push_str(help);
-            }
-            if arg.variadic {
-                helpmsg.push('\t');
-                helpmsg.push_str("[variadic]");
-            }
-            if !arg.required {
-                helpmsg.push('\t');
-                helpmsg.push_str("[optional]");
-            }
-            helpmsg.push('\n');
+        /// The default and possible values of an argument that takes a value, for `--help`.
+        fn details(arg: &Arg) -> Vec<TokenStream2> {
+            let default = arg.default();
+            let values = arg.values();
+            vec![
+                quote! { ::std::option::Option::<::std::string::String>::map(#default, |d| format!("[default: {}]", d)) },
+                quote! { #values.map(|v| format!("[possible values: {}]", v.join(", "))) },
+            ]
         }
-        for arg in &self.named_args {
-            helpmsg.push_str("    ");
-            if let Some(short) = &arg.short {
-                helpmsg.push_str(short);
-                helpmsg.push_str(", ");
-            }
-            helpmsg.push_str(&arg.arg_name);
-            if let Some(help) = &arg.help {
-                helpmsg.push('\t');
-                helpmsg.push_str(help);
+        /// The name of a named argument or flag, including its short form.
+        fn flag_name(arg: &Arg) -> String {
+            match &arg.short {
+                Some(short) => format!("{}, {}", short, arg.arg_name),
+                None => arg.arg_name.clone(),
             }
-            if !arg.required {
-                helpmsg.push('\t');
-                helpmsg.push_str("[optional]");
-            }
-            helpmsg.push('\n');
         }
-        if !args_empty {
-            helpmsg.push('\n');
+
+        // Help info for arguments.
+        let mut args = Vec::new();
+        for arg in &self.pos_args {
+            let tags = [(arg.variadic, "[variadic]"), (!arg.required, "[optional]")];
+            args.push((arg.name.clone(), describe(arg, &tags).with_details(details(arg))));
+        }
+        for arg in &self.named_args {
+            let help = describe(arg, &[(!arg.required, "[optional]")]).with_details(details(arg));
+            args.push((flag_name(arg), help));
         }
         // Help info for flags.
-        if !self.flags.is_empty() {
-            helpmsg.push_str("FLAGS:\n");
+        let flags = self
+            .flags
+            .iter()
+            .map(|flag| (flag_name(flag), describe(flag, &[])))
+            .collect();
+        vec![
+            HelpSection {
+                heading: "ARGUMENTS",
+                entries: args,
+            },
+            HelpSection {
+                heading: "FLAGS",
+                entries: flags,
+            },
+        ]
+    }
+    ///
+    /// The arguments in this command's usage line, such as `[OPTIONS] <PATTERN> [FILE]...`.
+    ///
+    /// Required named arguments are shown on their own; optional ones and flags are summarized as `[OPTIONS]`.
+    pub fn usage(&self) -> Vec<String> {
+        let mut usage = Vec::new();
+        if !self.flags.is_empty() || self.named_args.iter().any(|a| !a.required) {
+            usage.push("[OPTIONS]".to_string());
         }
-        for flag in &self.flags {
-            helpmsg.push_str("    ");
-            if let Some(short) = &flag.short {
-                helpmsg.push_str(short);
-                helpmsg.push_str(", ");
-            }
-            helpmsg.push_str(&flag.arg_name);
-            if let Some(help) = &flag.help {
-                helpmsg.push('\t');
-                helpmsg.push_str(help);
-            }
-            helpmsg.push('\n');
+        for arg in self.named_args.iter().filter(|a| a.required) {
+            usage.push(format!("{} <{}>", arg.arg_name, arg.name.to_uppercase()));
+        }
+        for arg in &self.pos_args {
+            usage.push(crate::usage_arg(&arg.name, arg.required, arg.variadic));
         }
+        usage
     }
     ///
+    /// The bounds that the type parameters `params` need for this command's arguments to be parsed.
+    pub fn bounds(&self, params: &[Ident]) -> Vec<TokenStream2> {
+        let args = self.pos_args.iter().chain(&self.named_args).map(|a| (a, false));
+        let flags = self.flags.iter().map(|a| (a, true));
+        args.chain(flags)
+            .flat_map(|(arg, flag)| crate::arg_bounds(&arg.ty, params, arg.required, arg.variadic, flag))
+            .collect()
+    }
+    ///
+    /// Code that builds a `ArgSpec` for each of this command's arguments.
+    pub fn spec_args(&self) -> Vec<TokenStream2> {
+        let krate = crate_path!();
+        
+        let pos_args = self.pos_args.iter().map(|a| (a, "Positional"));
+        let named_args = self.named_args.iter().map(|a| (a, "Named"));
+        let flags = self.flags.iter().map(|a| (a, "Flag"));
+        pos_args
+            .chain(named_args)
+            .chain(flags)
+            .map(|(arg, kind)| {
+                let Arg {
+                    name,
+                    arg_name,
+                    short,
+                    help,
+                    ty,
+                    required,
+                    variadic,
+                    complete,
+                    ..
+                } = arg;
+                let long = if kind == "Positional" {
+                    quote! { None }
+                } else {
+                    quote! { Some(#arg_name) }
+                };
+                let short = match short {
+                    Some(short) => quote! { Some(#short) },
+                    None => quote! { None },
+                };
+                let (help, long_help) = help.as_ref().map_or(("", ""), |h| (&h.short, &h.long));
+                let values = if kind == "Flag" {
+                    quote! { None }
+                } else {
+                    arg.values()
+                };
+                let complete = crate::complete_fn(complete.as_ref());
+                let ty_name = crate::type_name(ty);
+                // The value of the field if the argument isn't passed.
+                let default = if kind == "Flag" {
+                    crate::default_text(ty, quote! { <#ty as ::std::default::Default>::default() })
+                } else {
+                    arg.default()
+                };
+                // Flags and variadic arguments can always be left out.
+                let required = *required && !*variadic && kind != "Flag";
+                let kind = format_ident!("{}", kind);
+                quote! {
+                    #krate::ArgSpec {
+                        kind: #krate::ArgKind::#kind,
+                        name: #name,
+                        long: #long,
+                        short: #short,
+                        help: #help,
+                        long_help: #long_help,
+                        ty: #ty_name,
+                        required: #required,
+                        variadic: #variadic,
+                        default: #default,
+                        values: #values,
+                        complete: #complete,
+                    }
+                }
+            })
+            .collect()
+    }
+    ///
+    /// Convert this parser into ctor code for a CLI parser.
     ///
-    pub fn into_ctor(self, iter: &Ident, help_ident: &Ident) -> TokenStream2 {
-        let arg_ty = crate_path!(Argument);
+    /// The command line is split into the values of each argument by `engine::scan`, which is driven by a constant `Layout`,
+    /// so the only code generated for each argument is the code that parses its value.
+    pub fn into_ctor(
+        self,
+        iter: &Ident,
+        errors: &Ident,
+        help_ident: &Ident,
+        version: Option<&TokenStream2>,
+    ) -> TokenStream2 {
         let opt_ty = crate_path!(OptionalArg);
+        let var_ty = crate_path!(VariadicArg);
+        let flag_ty = crate_path!(Flag);
         let parse_ty = crate_path!(Parse);
-        let help_ty = crate_path!(HelpInfo);
+        let version_ty = crate_path!(VersionInfo);
         let err_ty = crate_path!(Error);
         let argref_ty = crate_path!(ArgRef);
+        let krate = crate_path!();
+        let engine = quote! { #krate::engine };
+        let positional_vals = format_ident!("ARGS_POSITIONALS");
+        let variadic_vals = format_ident!("ARGS_VARIADIC");
+        let named_vals = format_ident!("ARGS_NAMED");
+        let flag_counts = format_ident!("ARGS_FLAGS");
+        let tokens = format_ident!("ARGS_TOKENS");
+        let extra_vals = format_ident!("ARGS_EXTRA");
 
         let Self {
             cmd_ident,
@@ -192,195 +344,150 @@ impl Parser {
             named_args,
             flags,
         } = self;
-        let mut declarations = quote! {
-            let mut #iter = #iter.peekable();
-        };
-        // Code snippet to consume named arguments and flags.
-        let consume_flags = {
-            let mut match_args = quote! {};
-            for Arg {
-                arg_name,
-                short,
-                l_ident,
-                ..
-            } in &named_args
-            {
-                declarations = quote! {
-                    #declarations
-                    let mut #l_ident: Option<String> = None;
-                };
-                let mut pattern = quote! { Some(#arg_name) };
-                if let Some(short) = short {
-                    pattern = quote! { #pattern | Some(#short) };
-                }
-                match_args = quote! {
-                    #match_args
-                    #pattern => #l_ident = Some(#iter.next().ok_or(#err_ty::ExpectedValue(#arg_name))?) ,
-                }
-            }
-            let mut match_flags = quote! {};
-            let flag_ty = crate_path!(Flag);
-            for Arg {
-                arg_name: flag,
-                short,
-                l_ident,
-                ty,
-                ..
-            } in flags.iter()
-            {
-                declarations = quote! {
-                    #declarations
-                    let mut #l_ident = <#ty>::default();
-                };
-                let mut pattern = quote! { Some(#flag) };
-                if let Some(short) = short {
-                    pattern = quote! { #pattern | Some(#short) };
-                }
-                match_flags = quote! {
-                    #match_flags
-                    #pattern => #flag_ty::increment(&mut #l_ident) ,
-                };
-            }
+        let pos_len = pos_args.len();
+        let named_len = named_args.len();
+        let flags_len = flags.len();
 
-            let match_ = quote! {
-                match #iter.next().as_deref() {
-                    #match_args
-                    #match_flags
-                    Some("--help") | Some("-h") => return Ok(#parse_ty::Help(#help_ty(#help_ident))) ,
-                    Some(fl) => return Err(#err_ty::UnknownFlag(fl.to_string())),
-                    _ => panic!("This shouldn't happen."),
-                }
+        // The layout of the arguments, for the parser.
+        let layout = {
+            let positionals = pos_args.iter().map(|Arg { required, variadic, .. }| {
+                quote! { #engine::Positional { required: #required, variadic: #variadic } }
+            });
+            let name = |Arg { arg_name, short, .. }: &Arg| {
+                let short = match short {
+                    Some(short) => quote! { Some(#short) },
+                    None => quote! { None },
+                };
+                quote! { #engine::Name { long: #arg_name, short: #short } }
             };
+            let named = named_args.iter().map(name);
+            let flags = flags.iter().map(name);
+            let version = version.is_some();
             quote! {
-                while #iter.peek().map_or(false, |a| a.starts_with('-')) {
-                    #match_
+                #engine::Layout {
+                    positionals: &[#(#positionals),*],
+                    named: &[#(#named),*],
+                    flags: &[#(#flags),*],
+                    version: #version,
                 }
             }
         };
-        //
-        // Display the help message if called with no arguments.
-        // If all of the arguments are optional, don't do this.
-        let help_on_blank = if pos_args.iter().any(|a| a.required && !a.variadic) {
-            quote! {
-                if #iter.peek().is_none() {
-                    return Ok(#parse_ty::Help(#help_ty(#help_ident)));
-                }
-            }
-        } else {
-            quote! {}
+        let scan_version = match version {
+            Some(version) => quote! { return Ok(#parse_ty::Version(#version_ty::new(#errors.program(), #version))) },
+            None => quote! { unreachable!() },
         };
-        //
-        // Code to consume positional arguments.
-        let mut pos = quote! {};
+
+        // Code to parse the value of each argument.
+        // Each value is wrapped in an `Option`, which is `None` if an error was recorded.
+        let mut values = quote! {};
         for (i, arg) in pos_args.iter().enumerate() {
             let &Arg {
                 ref l_ident,
+                ref ty,
                 required,
                 variadic,
                 ..
             } = arg;
-            let i = i + 1;
-            // Variadic arguments.
-            if variadic {
-                declarations = quote! {
-                    #declarations
-                    let mut #l_ident = Vec::<String>::new();
-                };
-                pos = quote! {
-                    #pos
-                    while let Some(arg) = #iter.next() {
-                        #l_ident.push(arg);
-                        #consume_flags
-                    }
-                };
+            let position = i + 1;
+            // Collect args if variadic.
+            values = if variadic {
+                let parse = crate::parse_arg(
+                    quote! { <#ty as #var_ty>::Item },
+                    quote! { val },
+                    quote! { #argref_ty::Positional(#position + i) },
+                );
+                quote! {
+                    #values
+                    let #l_ident: Option<#ty> = #errors.collect(
+                        #variadic_vals.into_iter()
+                            .enumerate()
+                            .map(|(i, val)| #parse)
+                    )?;
+                }
             }
-            // Required arguments.
+            // Missing required arguments have already been recorded.
             else if required {
-                declarations = quote! {
-                    #declarations
-                    let #l_ident : String;
-                };
-                pos = quote! {
-                    #pos
-                    #l_ident = #iter.next().ok_or(#err_ty::ExpectedPositional(#i))?;
-                    #consume_flags
-                };
+                let parse = crate::parse_arg(ty, quote! { val }, quote! { #argref_ty::Positional(#position) });
+                quote! {
+                    #values
+                    let #l_ident: Option<#ty> = match #positional_vals[#i].take() {
+                        Some(val) => #errors.check(#parse)?,
+                        None => None,
+                    };
+                }
             }
-            // Optional arguments.
+            // Allow defaults if optional.
             else {
-                declarations = quote! {
-                    #declarations
-                    let mut #l_ident: Option<String> = None;
-                };
-                pos = quote! {
-                    #pos
-                    if let Some(next) = #iter.next() {
-                        #l_ident = Some(next);
-                        #consume_flags
-                    }
-                };
-            }
-        }
-
-        // Code to put the arguments in the constructor.
-        let ctor = {
-            let mut ctor = quote! {};
-            for (i, arg) in pos_args.into_iter().enumerate() {
-                let Arg {
-                    ident,
-                    l_ident,
-                    required,
-                    variadic,
-                    ..
-                } = arg;
-                let i = i + 1;
-                // Collect args if variadic.
-                ctor = if variadic {
-                    quote! {
-                        #ctor
-                        #ident : #l_ident.iter()
-                            .enumerate()
-                            .map(|(i, val)| #arg_ty::parse(val, #argref_ty::Positional(#i + i)))
-                            .collect::<Result<_, #err_ty>>()? ,
-                    }
-                }
-                // Handle errors if required.
-                else if required {
-                    quote! {
-                        #ctor
-                        #ident : #arg_ty::parse(#l_ident, #argref_ty::Positional(#i))? ,
-                    }
-                }
-                // Allow defaults if optional.
-                else {
-                    quote! {
-                        #ctor
-                        #ident: #opt_ty::map_parse(#l_ident, #argref_ty::Positional(#i))? ,
-                    }
+                let parse = crate::parse_arg(
+                    quote! { <#ty as #opt_ty>::Value },
+                    quote! { val },
+                    quote! { #argref_ty::Positional(#position) },
+                );
+                quote! {
+                    #values
+                    let #l_ident: Option<#ty> = match #positional_vals[#i].take() {
+                        Some(val) => #errors.check(#parse)?.map(<#ty as #opt_ty>::some),
+                        None => Some(<#ty as #opt_ty>::default()),
+                    };
                 }
             }
-            for Arg {
+        }
+        for (
+            i,
+            Arg {
                 arg_name,
-                ident,
                 l_ident,
+                ty,
                 required,
                 ..
-            } in named_args
-            {
-                let argref = quote! { #argref_ty::Named(#arg_name) };
-                // Error handling if it's required.
-                ctor = if required {
-                    quote! {
-                        #ctor
-                        #ident: #arg_ty::parse(#l_ident.ok_or(#err_ty::ExpectedNamed(#arg_name))?, #argref)? ,
-                    }
+            },
+        ) in named_args.iter().enumerate()
+        {
+            let argref = quote! { #argref_ty::Named(#arg_name) };
+            // Error handling if it's required.
+            values = if *required {
+                let parse = crate::parse_arg(ty, quote! { val }, argref);
+                quote! {
+                    #values
+                    let #l_ident: Option<#ty> = match #named_vals[#i].take() {
+                        Some(val) => #errors.check(#parse)?,
+                        None => {
+                            #errors.push(#err_ty::expected_named(#arg_name))?;
+                            None
+                        }
+                    };
                 }
-                // Defaults if it's optional
-                else {
-                    quote! {
-                        #ctor
-                        #ident: #opt_ty::map_parse(#l_ident, #argref)? ,
-                    }
+            }
+            // Defaults if it's optional
+            else {
+                let parse = crate::parse_arg(quote! { <#ty as #opt_ty>::Value }, quote! { val }, argref);
+                quote! {
+                    #values
+                    let #l_ident: Option<#ty> = match #named_vals[#i].take() {
+                        Some(val) => #errors.check(#parse)?.map(<#ty as #opt_ty>::some),
+                        None => Some(<#ty as #opt_ty>::default()),
+                    };
+                }
+            }
+        }
+        for (i, Arg { l_ident, ty, .. }) in flags.iter().enumerate() {
+            values = quote! {
+                #values
+                let mut #l_ident = <#ty>::default();
+                for _ in 0..#flag_counts[#i] {
+                    #flag_ty::increment(&mut #l_ident);
+                }
+            };
+        }
+
+        // Code to put the arguments in the constructor.
+        // Every value is `Some` once we know there were no errors.
+        let ctor = {
+            let mut ctor = quote! {};
+            for Arg { ident, l_ident, .. } in pos_args.iter().chain(&named_args) {
+                ctor = quote! {
+                    #ctor
+                    #ident: #l_ident.unwrap() ,
                 }
             }
             for Arg { ident, l_ident, .. } in flags {
@@ -395,17 +502,42 @@ impl Parser {
             }
         };
 
+        // Storage for the values of each argument, which is filled in by the parser.
+        let storage = {
+            let positionals = pos_args.iter().map(|_| quote! { None });
+            let named = named_args.iter().map(|_| quote! { None });
+            quote! {
+                let mut #positional_vals: [Option<_>; #pos_len] = [#(#positionals),*];
+                let mut #variadic_vals = Vec::new();
+                let mut #named_vals: [Option<_>; #named_len] = [#(#named),*];
+                let mut #flag_counts = [0usize; #flags_len];
+            }
+        };
+
+        let extra = crate::lossy(quote! { a });
         quote! {{
-            #declarations
-            #help_on_blank
-            #consume_flags
-            #pos
-            let val = #ctor;
-            // Return an error if there's an extra argument at the end.
-            if let Some(a) = #iter.next() {
-                return Err(#err_ty::ExtraArg(a));
+            const LAYOUT: #engine::Layout<'static> = #layout;
+            #storage
+            let #tokens = #engine::Tokens {
+                positionals: &mut #positional_vals,
+                variadic: &mut #variadic_vals,
+                named: &mut #named_vals,
+                flags: &mut #flag_counts,
+            };
+            let #extra_vals = match #engine::scan(LAYOUT, #iter, #tokens, &mut #errors)? {
+                #engine::Scan::Args { extra } => extra,
+                #engine::Scan::Help { requested, kind } => {
+                    return Ok(#parse_ty::Help(#help_ident(#errors.program()).shown(requested, kind)))
+                }
+                #engine::Scan::Version => #scan_version,
+            };
+            #values
+            // Record an error if there are extra arguments at the end.
+            for a in #extra_vals {
+                #errors.push(#err_ty::extra_arg(#extra))?;
             }
-            val
+            #errors.finish()?;
+            #ctor
         }}
     }
 }
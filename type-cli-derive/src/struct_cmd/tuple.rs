@@ -1,10 +1,12 @@
 use proc_macro2::TokenStream as TokenStream2;
-use quote::quote;
-use syn::{self, Ident};
+use quote::{format_ident, quote};
+use syn::{self, Ident, Type};
 
 struct Arg {
+    ty: Type,
     required: bool,
     variadic: bool,
+    complete: Option<syn::Path>,
 }
 
 pub(super) struct Parser {
@@ -13,70 +15,195 @@ pub(super) struct Parser {
 }
 impl Parser {
     /// Process the fields of the tuple struct from `syn` into a form relevant to CLI.
-    pub fn collect_args(cmd_ident: Ident, fields: syn::FieldsUnnamed) -> Self {
+    pub fn collect_args(cmd_ident: Ident, fields: syn::FieldsUnnamed) -> syn::Result<Self> {
         let mut args: Vec<Arg> = Vec::new();
-        for (i, syn::Field { attrs, .. }) in fields.unnamed.into_iter().enumerate() {
-            if args.last().map_or(false, |a| a.variadic) {
+        for (i, syn::Field { attrs, ty, .. }) in fields.unnamed.into_iter().enumerate() {
+            if args.last().is_some_and(|a| a.variadic) {
                 panic!("Variadic arguments must come last.");
             }
             let required = !attrs.iter().any(|a| a.path.is_ident("optional"));
-            if required && args.last().map_or(false, |a| !a.required) {
+            if required && args.last().is_some_and(|a| !a.required) {
                 panic!(
                     "Required argument at position `{}` must come before any optional arguments.",
                     i + 1
                 );
             }
             let variadic = attrs.iter().any(|a| a.path.is_ident("variadic"));
-            args.push(Arg { required, variadic });
+            let complete = crate::parse_complete_with(&attrs)?;
+            args.push(Arg {
+                ty,
+                required,
+                variadic,
+                complete,
+            });
         }
 
-        Self { cmd_ident, args }
+        Ok(Self { cmd_ident, args })
+    }
+    /// The arguments in this command's usage line, such as `<ARG1> [ARG2]`.
+    pub fn usage(&self) -> Vec<String> {
+        self.args
+            .iter()
+            .enumerate()
+            .map(|(i, a)| crate::usage_arg(&format!("arg{}", i + 1), a.required, a.variadic))
+            .collect()
+    }
+    /// The bounds that the type parameters `params` need for this command's arguments to be parsed.
+    pub fn bounds(&self, params: &[Ident]) -> Vec<TokenStream2> {
+        self.args
+            .iter()
+            .flat_map(|a| crate::arg_bounds(&a.ty, params, a.required, a.variadic, false))
+            .collect()
+    }
+    /// Code that builds a `ArgSpec` for each of this command's arguments.
+    pub fn spec_args(&self) -> Vec<TokenStream2> {
+        let krate = crate_path!();
+        let opt_ty = crate_path!(OptionalArg);
+        let var_ty = crate_path!(VariadicArg);
+        self.args
+            .iter()
+            .enumerate()
+            .map(|(i, Arg { ty, required, variadic, complete })| {
+                let name = format!("arg{}", i + 1);
+                let values = if *variadic {
+                    crate::possible_values(quote! { <#ty as #var_ty>::Item })
+                } else if !required {
+                    crate::possible_values(quote! { <#ty as #opt_ty>::Value })
+                } else {
+                    crate::possible_values(ty)
+                };
+                let complete = crate::complete_fn(complete.as_ref());
+                let ty_name = crate::type_name(ty);
+                let default = if *required || *variadic {
+                    quote! { None }
+                } else {
+                    crate::optional_default(ty)
+                };
+                let required = *required && !*variadic;
+                quote! {
+                    #krate::ArgSpec {
+                        kind: #krate::ArgKind::Positional,
+                        name: #name,
+                        long: None,
+                        short: None,
+                        help: "",
+                        long_help: "",
+                        ty: #ty_name,
+                        required: #required,
+                        variadic: #variadic,
+                        default: #default,
+                        values: #values,
+                        complete: #complete,
+                    }
+                }
+            })
+            .collect()
     }
     /// Convert this parser into ctor code for a CLI parser.
-    pub fn into_ctor(self, iter: &Ident, _help_ident: &Ident) -> TokenStream2 {
-        let arg_ty = crate_path!(Argument);
+    pub fn into_ctor(
+        self,
+        iter: &Ident,
+        errors: &Ident,
+        _help_ident: &Ident,
+        version: Option<&TokenStream2>,
+    ) -> TokenStream2 {
         let opt_ty = crate_path!(OptionalArg);
+        let var_ty = crate_path!(VariadicArg);
         let err_ty = crate_path!(Error);
         let argref_ty = crate_path!(ArgRef);
 
         let Self { cmd_ident, args } = self;
+        // Code to parse the value of each argument.
+        // Each value is wrapped in an `Option`, which is `None` if an error was recorded.
+        let mut values = quote! {};
         let mut ctor = quote! {};
-        for (i, Arg { required, variadic }) in args.into_iter().enumerate() {
+        for (
+            i,
+            Arg {
+                ty,
+                required,
+                variadic,
+                ..
+            },
+        ) in args.into_iter().enumerate()
+        {
+            let val_ident = format_ident!("val_{}", i);
             let i = i + 1;
             // Variadic arguments.
-            ctor = if variadic {
+            values = if variadic {
+                let parse = crate::parse_arg(
+                    quote! { <#ty as #var_ty>::Item },
+                    quote! { val },
+                    quote! { #argref_ty::Positional(#i + i) },
+                );
                 // Run collect `by_ref` so it doesn't move the iterator.
                 quote! {
-                    #ctor
-                    #iter.by_ref()
-                        .enumerate()
-                        .map(|(i, val)| #arg_ty::parse(val, #argref_ty::Positional(#i + i)))
-                        .collect::<Result<_, #err_ty>>()? ,
+                    #values
+                    let #val_ident: Option<#ty> = #errors.collect(
+                        #iter.by_ref()
+                            .enumerate()
+                            .map(|(i, val)| #parse)
+                    )?;
                 }
             }
             // Required arguments.
             else if required {
+                let parse = crate::parse_arg(&ty, quote! { val }, quote! { #argref_ty::Positional(#i) });
                 quote! {
-                    #ctor
-                    #arg_ty::parse(#iter.next().ok_or(#err_ty::ExpectedPositional(#i))?, #argref_ty::Positional(#i))? ,
+                    #values
+                    let #val_ident: Option<#ty> = match #iter.next() {
+                        Some(val) => #errors.check(#parse)?,
+                        None => {
+                            #errors.push(#err_ty::expected_positional(#i))?;
+                            None
+                        }
+                    };
                 }
             }
             // Optional arguments.
             else {
+                let parse = crate::parse_arg(
+                    quote! { <#ty as #opt_ty>::Value },
+                    quote! { val },
+                    quote! { #argref_ty::Positional(#i) },
+                );
                 quote! {
-                    #ctor
-                    #opt_ty::map_parse(#iter.next(), #argref_ty::Positional(#i))? ,
+                    #values
+                    let #val_ident: Option<#ty> = match #iter.next() {
+                        Some(val) => #errors.check(#parse)?.map(<#ty as #opt_ty>::some),
+                        None => Some(<#ty as #opt_ty>::default()),
+                    };
                 }
-            }
+            };
+            // Every value is `Some` once we know there were no errors.
+            ctor = quote! {
+                #ctor
+                #val_ident.unwrap() ,
+            };
         }
+        // Tuple structs don't have flags, so only check for `--version` before the first argument.
+        let check_version = version.map(|_| {
+            let first = crate::as_str(quote! { #iter.peek() });
+            let match_version = crate::match_version(version, errors);
+            quote! {
+                match #first {
+                    #match_version
+                    _ => {}
+                }
+            }
+        });
+        let extra = crate::lossy(quote! { a });
         quote! {
-            let val = #cmd_ident (
-                #ctor
-            );
-            if let Some(a) = #iter.next() {
-                return Err(#err_ty::ExtraArg(a));
+            let mut #iter = #iter.peekable();
+            #check_version
+            #values
+            for a in #iter {
+                #errors.push(#err_ty::extra_arg(#extra))?;
             }
-            val
+            #errors.finish()?;
+            #cmd_ident (
+                #ctor
+            )
         }
     }
 }
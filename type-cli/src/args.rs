@@ -1,40 +1,354 @@
-use std::{convert::AsRef, str::FromStr};
+use std::{borrow::Cow, convert::AsRef, ffi::{OsStr, OsString}, marker::PhantomData, path::{Path, PathBuf}, str::FromStr};
 use super::{Error, ArgRef};
 use std::error::Error as StdError;
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, LinkedList, VecDeque};
+use std::hash::{BuildHasher, Hash};
 
+///
+/// A value that can be parsed from a single command-line argument.
+///
+/// Any type that implements `FromStr` can be used as an argument without implementing this trait.
+/// Implement it directly to give your own types custom parsing;
+/// an impl of `Argument` always takes priority over `FromStr`.
 pub trait Argument : Sized {
-    fn parse(arg: impl AsRef<str>, arg: ArgRef) -> Result<Self, Error>;
+    fn parse(val: impl AsRef<str>, arg: ArgRef) -> Result<Self, Error>;
+    ///
+    /// Every value this argument can take, if there are only a few, such as the names of an enum's variants.
+    ///
+    /// These are offered by shell completions.
+    fn possible_values() -> Option<&'static [&'static str]> {
+        None
+    }
+}
+
+///
+/// A value that can be parsed from a single command-line argument, which might not be valid unicode.
+///
+/// This is implemented for `OsString` and `PathBuf`, which receive the argument's raw bytes.
+/// It takes priority over both `Argument` and `FromStr`.
+pub trait ArgumentOs : Sized {
+    fn parse_os(val: OsString, arg: ArgRef) -> Result<Self, Error>;
+}
+
+impl ArgumentOs for OsString {
+    fn parse_os(val: OsString, _: ArgRef) -> Result<Self, Error> {
+        Ok(val)
+    }
+}
+impl ArgumentOs for PathBuf {
+    fn parse_os(val: OsString, _: ArgRef) -> Result<Self, Error> {
+        Ok(val.into())
+    }
+}
+/// An empty value parses as `None`.
+impl<T: ArgumentOs> ArgumentOs for Option<T> {
+    fn parse_os(val: OsString, arg: ArgRef) -> Result<Self, Error> {
+        if val.is_empty() {
+            Ok(None)
+        } else {
+            T::parse_os(val, arg).map(Some)
+        }
+    }
+}
+
+///
+/// A value that can borrow from a command-line argument, for commands parsed with `ParseBorrowed::parse_borrowed`.
+///
+/// This is implemented for `&str`, `&OsStr` and `&Path`, which point into the arguments instead of copying them.
+/// It takes priority over `ArgumentOs`, `Argument` and `FromStr`.
+pub trait BorrowedArgument<'a> : Sized {
+    fn parse_borrowed(val: &'a str, arg: ArgRef) -> Result<Self, Error>;
 }
 
-impl<T: FromStr> Argument for T
-where <T as FromStr>::Err : StdError + 'static
+impl<'a> BorrowedArgument<'a> for &'a str {
+    fn parse_borrowed(val: &'a str, _: ArgRef) -> Result<Self, Error> {
+        Ok(val)
+    }
+}
+impl<'a> BorrowedArgument<'a> for &'a OsStr {
+    fn parse_borrowed(val: &'a str, _: ArgRef) -> Result<Self, Error> {
+        Ok(OsStr::new(val))
+    }
+}
+impl<'a> BorrowedArgument<'a> for &'a Path {
+    fn parse_borrowed(val: &'a str, _: ArgRef) -> Result<Self, Error> {
+        Ok(Path::new(val))
+    }
+}
+
+/// Convert an argument to a `String`, for types that can't be parsed from an `OsString`.
+pub(crate) fn into_string(val: OsString, arg: ArgRef) -> Result<String, Error> {
+    val.into_string().map_err(|val| Error::invalid_unicode(arg, &val))
+}
+
+///
+/// An argument as it was passed to the parser:
+/// an `OsString` from `CLI::parse_os`, or a `&str` from `ParseBorrowed::parse_borrowed`.
+///
+/// Borrowed arguments are never copied unless they're parsed into an owned type such as `String`.
+#[doc(hidden)]
+pub trait RawArg<'a> : Sized {
+    fn as_os_str(&self) -> &OsStr;
+    fn into_os_string(self) -> OsString;
+    fn into_str(self, arg: ArgRef) -> Result<Cow<'a, str>, Error>;
+}
+
+impl<'a> RawArg<'a> for OsString {
+    fn as_os_str(&self) -> &OsStr {
+        self
+    }
+    fn into_os_string(self) -> OsString {
+        self
+    }
+    fn into_str(self, arg: ArgRef) -> Result<Cow<'a, str>, Error> {
+        into_string(self, arg).map(Cow::Owned)
+    }
+}
+impl<'a> RawArg<'a> for &'a str {
+    fn as_os_str(&self) -> &OsStr {
+        OsStr::new(self)
+    }
+    fn into_os_string(self) -> OsString {
+        self.into()
+    }
+    fn into_str(self, _: ArgRef) -> Result<Cow<'a, str>, Error> {
+        Ok(Cow::Borrowed(self))
+    }
+}
+
+fn from_str<T: FromStr>(val: &str, arg: ArgRef) -> Result<T, Error>
+where <T as FromStr>::Err : StdError + Send + Sync + 'static
 {
+    T::from_str(val).map_err(|e| Error::parse(arg, val, e))
+}
+
+macro_rules! from_str_arg {
+    ($($ty: ty),* $(,)?) => {
+        $(
+            impl $crate::Argument for $ty {
+                fn parse(val: impl AsRef<str>, arg: ArgRef) -> Result<Self, Error> {
+                    from_str(val.as_ref(), arg)
+                }
+            }
+        )*
+    }
+}
+
+from_str_arg!(String, PathBuf, OsString, bool, char);
+from_str_arg!(usize, u8, u16, u32, u64, u128, isize, i8, i16, i32, i64, i128, f32, f64);
+from_str_arg!(
+    std::num::NonZeroUsize, std::num::NonZeroU8, std::num::NonZeroU16,
+    std::num::NonZeroU32, std::num::NonZeroU64, std::num::NonZeroU128,
+);
+from_str_arg!(
+    std::num::NonZeroIsize, std::num::NonZeroI8, std::num::NonZeroI16,
+    std::num::NonZeroI32, std::num::NonZeroI64, std::num::NonZeroI128,
+);
+from_str_arg!(
+    std::net::IpAddr, std::net::Ipv4Addr, std::net::Ipv6Addr,
+    std::net::SocketAddr, std::net::SocketAddrV4, std::net::SocketAddrV6,
+);
+
+/// An empty value parses as `None`.
+impl<T: Argument> Argument for Option<T> {
     fn parse(val: impl AsRef<str>, arg: ArgRef) -> Result<Self, Error> {
         let val = val.as_ref();
-        T::from_str(val).map_err(|e| Error::Parse(arg, Box::new(e)))
+        if val.is_empty() {
+            Ok(None)
+        } else {
+            T::parse(val, arg).map(Some)
+        }
+    }
+    fn possible_values() -> Option<&'static [&'static str]> {
+        T::possible_values()
+    }
+}
+
+/// The error returned when a tuple argument has too few comma-separated values.
+#[derive(Debug, thiserror::Error)]
+#[error("Expected {expected} comma-separated values, found {found}")]
+struct TupleLen {
+    expected: usize,
+    found: usize,
+}
+
+macro_rules! tuple_arg {
+    ($len: literal; $($T: ident $val: ident),*) => {
+        /// Tuples are parsed from comma-separated values: `a,b`.
+        /// The last element receives any leftover commas.
+        impl<$($T: Argument),*> Argument for ($($T,)*) {
+            fn parse(val: impl AsRef<str>, arg: ArgRef) -> Result<Self, Error> {
+                let mut vals = val.as_ref().splitn($len, ',');
+                $(
+                    let $val = vals.next();
+                )*
+                match ($($val,)*) {
+                    ($(Some($val),)*) => Ok(($($T::parse($val, arg)?,)*)),
+                    ($($val,)*) => {
+                        let found = [$($val.is_some()),*].iter().filter(|&&s| s).count();
+                        Err(Error::parse(arg, val.as_ref(), TupleLen { expected: $len, found }))
+                    }
+                }
+            }
+        }
+    }
+}
+
+tuple_arg!(2; A a, B b);
+tuple_arg!(3; A a, B b, C c);
+tuple_arg!(4; A a, B b, C c, D d);
+
+
+///
+/// Selects how a value is parsed from an argument.
+/// `BorrowedArgument` is preferred, then `ArgumentOs`, then `Argument`, falling back to `FromStr`.
+///
+/// This is used by the code generated from `#[derive(CLI)]`:
+/// ```ignore
+/// (&&&&ArgParser::<T>::new()).parse_arg(val, arg)
+/// ```
+/// The possible values of an argument are found the same way,
+/// so only types that implement `Argument` can have them.
+#[doc(hidden)]
+pub struct ArgParser<T>(PhantomData<T>);
+
+impl<T> ArgParser<T> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+impl<T> Default for ArgParser<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Only arguments passed as `&str` can be borrowed, so `val` isn't a `RawArg`.
+#[doc(hidden)]
+pub trait ViaBorrowed<'a, T> {
+    fn parse_arg(&self, val: &'a str, arg: ArgRef) -> Result<T, Error>;
+    fn possible_values(&self) -> Option<&'static [&'static str]> {
+        None
+    }
+}
+impl<'a, T: BorrowedArgument<'a>> ViaBorrowed<'a, T> for &&&ArgParser<T> {
+    fn parse_arg(&self, val: &'a str, arg: ArgRef) -> Result<T, Error> {
+        T::parse_borrowed(val, arg)
     }
 }
 
+#[doc(hidden)]
+pub trait ViaArgumentOs<T> {
+    fn parse_arg<'a>(&self, val: impl RawArg<'a>, arg: ArgRef) -> Result<T, Error>;
+    fn possible_values(&self) -> Option<&'static [&'static str]> {
+        None
+    }
+}
+impl<T: ArgumentOs> ViaArgumentOs<T> for &&ArgParser<T> {
+    fn parse_arg<'a>(&self, val: impl RawArg<'a>, arg: ArgRef) -> Result<T, Error> {
+        T::parse_os(val.into_os_string(), arg)
+    }
+}
 
+#[doc(hidden)]
+pub trait ViaArgument<T> {
+    fn parse_arg<'a>(&self, val: impl RawArg<'a>, arg: ArgRef) -> Result<T, Error>;
+    fn possible_values(&self) -> Option<&'static [&'static str]>;
+}
+impl<T: Argument> ViaArgument<T> for &ArgParser<T> {
+    fn parse_arg<'a>(&self, val: impl RawArg<'a>, arg: ArgRef) -> Result<T, Error> {
+        T::parse(val.into_str(arg)?, arg)
+    }
+    fn possible_values(&self) -> Option<&'static [&'static str]> {
+        T::possible_values()
+    }
+}
+
+#[doc(hidden)]
+pub trait ViaFromStr<T> {
+    fn parse_arg<'a>(&self, val: impl RawArg<'a>, arg: ArgRef) -> Result<T, Error>;
+    fn possible_values(&self) -> Option<&'static [&'static str]> {
+        None
+    }
+}
+impl<T: FromStr> ViaFromStr<T> for ArgParser<T>
+where <T as FromStr>::Err : StdError + Send + Sync + 'static
+{
+    fn parse_arg<'a>(&self, val: impl RawArg<'a>, arg: ArgRef) -> Result<T, Error> {
+        from_str(&val.into_str(arg)?, arg)
+    }
+}
+
+
+///
+/// A type that can hold an `#[optional]` argument.
 pub trait OptionalArg : Sized {
-    fn parse(arg: impl AsRef<str>, arg: ArgRef) -> Result<Self, Error>;
+    /// The type parsed from the argument, if it was passed.
+    type Value;
+    fn some(val: Self::Value) -> Self;
     fn default() -> Self;
-
-    fn map_parse(val: Option<impl AsRef<str>>, arg: ArgRef) -> Result<Self, Error> {
-        match val {
-            Some(val) => Self::parse(val, arg),
-            None => Ok(Self::default())
-        }
+    /// Whether this holds a value, which help shows as the default of the argument.
+    #[doc(hidden)]
+    fn has_value(&self) -> bool {
+        true
     }
 }
 
-impl<T: Argument> OptionalArg for Option<T> {
-    fn parse(val: impl AsRef<str>, arg: ArgRef) -> Result<Self, Error> {
-        Some(T::parse(val, arg)).transpose()
+impl<T> OptionalArg for Option<T> {
+    type Value = T;
+    fn some(val: T) -> Self {
+        Some(val)
     }
     fn default() -> Self {
         None
     }
+    fn has_value(&self) -> bool {
+        self.is_some()
+    }
+}
+
+
+///
+/// A collection that can hold a `#[variadic]` argument.
+///
+/// Implementors must also implement `FromIterator<Self::Item>`.
+pub trait VariadicArg : Sized {
+    /// The type parsed from each argument.
+    type Item;
+}
+
+impl<T> VariadicArg for Vec<T> {
+    type Item = T;
+}
+impl<T> VariadicArg for VecDeque<T> {
+    type Item = T;
+}
+impl<T> VariadicArg for LinkedList<T> {
+    type Item = T;
+}
+impl<T: Ord> VariadicArg for BinaryHeap<T> {
+    type Item = T;
+}
+impl<T: Ord> VariadicArg for BTreeSet<T> {
+    type Item = T;
+}
+impl<T> VariadicArg for Box<[T]> {
+    type Item = T;
+}
+impl<T, S> VariadicArg for HashSet<T, S>
+where T: Eq + Hash, S: BuildHasher + Default
+{
+    type Item = T;
+}
+/// Each entry is parsed as a `key,value` tuple.
+impl<K: Ord, V> VariadicArg for BTreeMap<K, V> {
+    type Item = (K, V);
+}
+/// Each entry is parsed as a `key,value` tuple.
+impl<K, V, S> VariadicArg for HashMap<K, V, S>
+where K: Eq + Hash, S: BuildHasher + Default
+{
+    type Item = (K, V);
 }
 
 
@@ -0,0 +1,708 @@
+use crate::engine::{self, Layout, Name, Positional, Scan, Tokens};
+use crate::{
+    usage_line, ArgKind, ArgRef, Argument, Error, ErrorCollector, Errors, Exit, HelpEntry, HelpInfo, HelpKind, HelpSection,
+    HelpText, Parse, ProcessConfig, VersionInfo,
+};
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+use std::ffi::{OsStr, OsString};
+use std::sync::{Mutex, PoisonError};
+
+///
+/// A command defined at runtime, for commands that `#[derive(CLI)]` can't describe,
+/// such as ones read from a plugin's manifest.
+///
+/// A command has either arguments, like a struct that derives `CLI`, or subcommands, like an enum.
+/// It's parsed with the same rules as a derived command, into `Matches` instead of a struct,
+/// and its help and errors look the same.
+///
+/// ```ignore
+/// let cmd = Command::new("cargo")
+///     .version("1.0.0")
+///     .subcommand(
+///         Command::new("build")
+///             .about("Compile the current package.")
+///             .arg(Arg::named("target").short('t').optional().help("Build for the target triple."))
+///             .arg(Arg::flag("release").short('r').help("Build with optimizations.")),
+///     );
+/// let matches = cmd.process();
+/// if let Some(("build", build)) = matches.subcommand() {
+///     let target: Option<String> = build.value("target")?;
+/// }
+/// ```
+///
+/// Names built at runtime, such as the `--time-out` of `Arg::named("time_out")`, are kept for the rest of the program,
+/// since errors refer to arguments and subcommands by `&'static str`.
+/// Each distinct name is only kept once, so building the same command again doesn't use more memory.
+#[derive(Clone, Debug)]
+pub struct Command {
+    name: &'static str,
+    about: Option<Cow<'static, str>>,
+    long_about: Option<Cow<'static, str>>,
+    examples: Vec<Cow<'static, str>>,
+    env: Vec<(Cow<'static, str>, Cow<'static, str>)>,
+    version: Option<&'static str>,
+    args: Vec<Arg>,
+    subcommands: Vec<Command>,
+}
+
+impl Command {
+    /// A command called `name`. For the top-level command, this is the name of the program.
+    pub fn new(name: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            name: intern(name.into()),
+            about: None,
+            long_about: None,
+            examples: Vec::new(),
+            env: Vec::new(),
+            version: None,
+            args: Vec::new(),
+            subcommands: Vec::new(),
+        }
+    }
+    /// A description of the command, shown by `-h` and `--help`.
+    pub fn about(mut self, about: impl Into<Cow<'static, str>>) -> Self {
+        self.about = Some(about.into());
+        self
+    }
+    /// A longer description of the command, shown by `--help` instead of `about`.
+    pub fn long_about(mut self, about: impl Into<Cow<'static, str>>) -> Self {
+        self.long_about = Some(about.into());
+        self
+    }
+    /// An example of using the command, such as `cargo build -r`, shown by `--help`.
+    pub fn example(mut self, example: impl Into<Cow<'static, str>>) -> Self {
+        self.examples.push(example.into());
+        self
+    }
+    /// An environment variable that the command reads, listed by `--help` and in man pages.
+    pub fn env(mut self, name: impl Into<Cow<'static, str>>, description: impl Into<Cow<'static, str>>) -> Self {
+        self.env.push((name.into(), description.into()));
+        self
+    }
+    ///
+    /// Accept `--version` and `-V`, which show `version`.
+    /// This also applies to every subcommand, unless the subcommand has a version of its own.
+    ///
+    /// # Panics
+    /// If an argument of the command or of any subcommand is already called `--version` or `-V`.
+    pub fn version(mut self, version: &'static str) -> Self {
+        self.check_builtins(true);
+        self.version = Some(version);
+        self
+    }
+    ///
+    /// Add an argument.
+    ///
+    /// # Panics
+    /// If the command has subcommands, if a required positional argument comes after an optional one,
+    /// if a positional argument comes after a variadic one, if the argument is called `--help` or `-h`
+    /// (or `--version` or `-V` when the command has a version), or if another argument has the same name.
+    pub fn arg(mut self, arg: Arg) -> Self {
+        assert!(
+            self.subcommands.is_empty(),
+            "Command `{}` can't have both arguments and subcommands.",
+            self.name
+        );
+        arg.check_builtin(self.version.is_some());
+        if let Some((other, name)) = self.args.iter().find_map(|a| arg.clash(a).map(|name| (a, name))) {
+            panic!("Argument `{}` can't use `{}`, which is already used by argument `{}`", arg.name, name, other.name);
+        }
+        if arg.kind == ArgKind::Positional {
+            let last = self.args.iter().rev().find(|a| a.kind == ArgKind::Positional);
+            if last.is_some_and(|a| a.variadic) {
+                panic!("Positional argument `{}` must come before the variadic argument.", arg.name);
+            }
+            if arg.required && last.is_some_and(|a| !a.required) {
+                panic!("Required positional argument `{}` must come before any optional arguments.", arg.name);
+            }
+        }
+        self.args.push(arg);
+        self
+    }
+    ///
+    /// Add a subcommand.
+    ///
+    /// # Panics
+    /// If the command has arguments, or if the command has a version
+    /// and an argument of the subcommand is called `--version` or `-V`.
+    pub fn subcommand(mut self, cmd: Command) -> Self {
+        assert!(
+            self.args.is_empty(),
+            "Command `{}` can't have both arguments and subcommands.",
+            self.name
+        );
+        cmd.check_builtins(self.version.is_some());
+        self.subcommands.push(cmd);
+        self
+    }
+
+    ///
+    /// Parses the arguments as this command, returning errors as a value for manual handling.
+    ///
+    /// The first argument is the name that the program was run as, which is skipped.
+    pub fn parse(&self, args: impl IntoIterator<Item = impl Into<OsString>>) -> Result<Parse<Matches>, Error> {
+        self.parse_with(args, ErrorCollector::first())
+            .map_err(Errors::into_first)
+    }
+    ///
+    /// Parses the arguments as this command, continuing after an error so that every mistake can be reported at once.
+    ///
+    /// Help info is still returned as soon as `--help` or `-h` is found.
+    pub fn parse_all_errors(&self, args: impl IntoIterator<Item = impl Into<OsString>>) -> Result<Parse<Matches>, Errors> {
+        self.parse_with(args, ErrorCollector::all())
+    }
+    ///
+    /// Help info about this command, without parsing any arguments.
+    ///
+    /// This is the long help, as if the user had passed `--help`.
+    pub fn help(&self) -> HelpInfo {
+        self.help_for(self.name, &[])
+    }
+    ///
+    /// Help info about this command, with the help of each of its subcommands added.
+    ///
+    /// This is what `ManPage::from_help` needs to document every subcommand.
+    pub fn help_tree(&self) -> HelpInfo {
+        self.help_tree_for(self.name, &[])
+    }
+    ///
+    /// Parses `std::env::args_os()` as this command.
+    ///
+    /// Help and errors are reported the same way as `CLI::process`.
+    pub fn process(&self) -> Matches {
+        self.process_with(ProcessConfig::default())
+    }
+    ///
+    /// Parses `std::env::args_os()` as this command, reporting help and errors as described by `config`.
+    pub fn process_with(&self, config: ProcessConfig) -> Matches {
+        self.try_process_with(config).unwrap_or_else(|exit| exit.exit())
+    }
+    ///
+    /// Parses `std::env::args_os()` as this command.
+    ///
+    /// If help info or an error should be shown, it is returned as an `Exit`,
+    /// which prints it with the right exit code when returned from `main`.
+    pub fn try_process(&self) -> Result<Matches, Exit> {
+        self.try_process_with(ProcessConfig::default())
+    }
+    ///
+    /// Parses `std::env::args_os()` as this command, reporting help and errors as described by `config`.
+    pub fn try_process_with(&self, config: ProcessConfig) -> Result<Matches, Exit> {
+        self.try_process_from(config, std::env::args_os().collect())
+    }
+    ///
+    /// `Command::try_process_with`, given the arguments of the program, starting with its name.
+    #[doc(hidden)]
+    pub fn try_process_from(&self, config: ProcessConfig, args: Vec<OsString>) -> Result<Matches, Exit> {
+        match self.parse(args.iter().cloned()) {
+            Ok(Parse::Success(matches)) => Ok(matches),
+            // Only help and errors are styled, so `--color` is only looked for when they're shown.
+            other => {
+                let args = args.get(1..).unwrap_or_default();
+                config.color_arg(self.scan_named(args, self.version, "--color")).handle(other)
+            }
+        }
+    }
+
+    fn parse_with(
+        &self,
+        args: impl IntoIterator<Item = impl Into<OsString>>,
+        mut errors: ErrorCollector,
+    ) -> Result<Parse<Matches>, Errors> {
+        let mut args = args.into_iter().map(Into::into);
+        args.next();
+        errors.set_program(self.name);
+        let parse = self.parse_command(&[], self.version, &mut args, &mut errors)?;
+        if let Parse::Success(_) = parse {
+            errors.finish()?;
+        }
+        Ok(parse)
+    }
+    /// Parse the arguments of this command, which is reached by the subcommands in `path`.
+    fn parse_command(
+        &self,
+        path: &[&'static str],
+        version: Option<&'static str>,
+        args: &mut dyn Iterator<Item = OsString>,
+        errors: &mut ErrorCollector,
+    ) -> Result<Parse<Matches>, Errors> {
+        let help = |errors: &ErrorCollector, requested, kind| {
+            Ok(Parse::Help(self.help_for(errors.program(), path).shown(requested, kind)))
+        };
+        errors.set_usage(self.usage(path));
+
+        if !self.subcommands.is_empty() {
+            let next = args.next();
+            let next = next.as_deref().map(OsStr::to_string_lossy);
+            if let Some(sub) = next.as_deref().and_then(|n| self.subcommands.iter().find(|s| s.name == n)) {
+                errors.enter(sub.name);
+                let path = [path, &[sub.name]].concat();
+                return Ok(match sub.parse_command(&path, sub.version.or(version), args, errors)? {
+                    Parse::Success(matches) => Parse::Success(Matches {
+                        args: Vec::new(),
+                        subcommand: Some((sub.name, Box::new(matches))),
+                    }),
+                    other => other,
+                });
+            }
+            return match (next.as_deref(), version) {
+                (Some("--version"), Some(version)) | (Some("-V"), Some(version)) => {
+                    Ok(Parse::Version(VersionInfo::new(errors.program(), version)))
+                }
+                (Some("--help"), _) => help(errors, true, HelpKind::Long),
+                (Some("-h"), _) => help(errors, true, HelpKind::Short),
+                (None, _) => help(errors, false, HelpKind::Short),
+                (Some(sub), _) => {
+                    let known: Vec<_> = self.subcommands.iter().map(|s| s.name).collect();
+                    Err(errors.fatal(Error::unknown_sub(sub.to_string(), &known)))
+                }
+            };
+        }
+
+        let (positionals, named, flags) = self.layout();
+        let layout = Layout {
+            positionals: &positionals,
+            named: &named,
+            flags: &flags,
+            version: version.is_some(),
+        };
+        let mut values = vec![None; positionals.len()];
+        let mut variadic = Vec::new();
+        let mut named_values = vec![None; named.len()];
+        let mut flag_counts = vec![0; flags.len()];
+        let tokens = Tokens {
+            positionals: &mut values,
+            variadic: &mut variadic,
+            named: &mut named_values,
+            flags: &mut flag_counts,
+        };
+        let extra = match engine::scan(layout, args, tokens, errors)? {
+            Scan::Args { extra } => extra,
+            Scan::Help { requested, kind } => return help(errors, requested, kind),
+            // The parser only looks for `--version` if there is one.
+            Scan::Version => return Ok(Parse::Version(VersionInfo::new(errors.program(), version.unwrap()))),
+        };
+
+        let mut matches = Matches {
+            args: Vec::new(),
+            subcommand: None,
+        };
+        let mut values = values.into_iter();
+        let mut named = named_values.into_iter();
+        let mut flags = flag_counts.into_iter();
+        let mut position = 0;
+        for arg in &self.args {
+            let (values, occurrences, arg_ref) = match arg.kind {
+                ArgKind::Positional => {
+                    position += 1;
+                    let value = values.next().unwrap();
+                    let values = if arg.variadic {
+                        std::mem::take(&mut variadic)
+                    } else {
+                        value.into_iter().collect()
+                    };
+                    (values, 0, ArgRef::Positional(position))
+                }
+                ArgKind::Named => {
+                    let value = named.next().unwrap();
+                    if value.is_none() && arg.required {
+                        errors.push(Error::expected_named(arg.long))?;
+                    }
+                    (value.into_iter().collect(), 0, ArgRef::Named(arg.long))
+                }
+                ArgKind::Flag => (Vec::new(), flags.next().unwrap(), ArgRef::Named(arg.long)),
+            };
+            matches.args.push(Match {
+                name: arg.name,
+                arg: arg_ref,
+                values,
+                occurrences,
+            });
+        }
+        for extra in extra {
+            errors.push(Error::extra_arg(extra.to_string_lossy()))?;
+        }
+        Ok(Parse::Success(matches))
+    }
+
+    /// Make sure that no argument of this command or its subcommands takes the name of a built-in flag.
+    fn check_builtins(&self, version: bool) {
+        for arg in &self.args {
+            arg.check_builtin(version);
+        }
+        for sub in &self.subcommands {
+            sub.check_builtins(version);
+        }
+    }
+    ///
+    /// The value of the named argument called `long`, such as `--color`, as the parser reads it from `args`,
+    /// which come after the name of the program. Subcommands are followed to the command that's run.
+    fn scan_named(&self, args: &[OsString], version: Option<&'static str>, long: &str) -> Option<OsString> {
+        if !self.subcommands.is_empty() {
+            let (next, args) = args.split_first()?;
+            let sub = self.subcommands.iter().find(|s| *next == s.name)?;
+            return sub.scan_named(args, sub.version.or(version), long);
+        }
+        let (positionals, named, flags) = self.layout();
+        let layout = Layout {
+            positionals: &positionals,
+            named: &named,
+            flags: &flags,
+            version: version.is_some(),
+        };
+        engine::scan_named(layout, args.iter().cloned(), long)
+    }
+    /// The positional arguments, named arguments and flags of this command, for the parser.
+    fn layout(&self) -> (Vec<Positional>, Vec<Name>, Vec<Name>) {
+        let of_kind = |kind| self.args.iter().filter(move |a: &&Arg| a.kind == kind);
+        let positionals = of_kind(ArgKind::Positional)
+            .map(|a| Positional {
+                required: a.required,
+                variadic: a.variadic,
+            })
+            .collect();
+        let name = |a: &Arg| Name {
+            long: a.long,
+            short: a.short,
+        };
+        let named = of_kind(ArgKind::Named).map(name).collect();
+        let flags = of_kind(ArgKind::Flag).map(name).collect();
+        (positionals, named, flags)
+    }
+    ///
+    /// This command's usage line after the name of the program, such as `build [OPTIONS] <PATTERN> [FILE]...`.
+    ///
+    /// It starts with the subcommands in `path`, which lead to this command.
+    fn usage(&self, path: &[&'static str]) -> String {
+        let mut usage: Vec<String> = path.iter().map(|s| s.to_string()).collect();
+        if !self.subcommands.is_empty() {
+            usage.push("<COMMAND>".to_string());
+            return usage.join(" ");
+        }
+        let positionals = self.args.iter().filter(|a| a.kind == ArgKind::Positional);
+        let named = self.args.iter().filter(|a| a.kind == ArgKind::Named);
+        if self.args.iter().any(|a| a.kind == ArgKind::Flag || (a.kind == ArgKind::Named && !a.required)) {
+            usage.push("[OPTIONS]".to_string());
+        }
+        for arg in named.filter(|a| a.required) {
+            usage.push(format!("{} <{}>", arg.long, arg.name.to_uppercase()));
+        }
+        for arg in positionals {
+            let name = arg.name.to_uppercase();
+            usage.push(if arg.variadic {
+                format!("[{}]...", name)
+            } else if arg.required {
+                format!("<{}>", name)
+            } else {
+                format!("[{}]", name)
+            });
+        }
+        usage.join(" ")
+    }
+    /// Help info for this command and each of its subcommands, for man pages.
+    fn help_tree_for(&self, program: &str, path: &[&'static str]) -> HelpInfo {
+        let mut help = self.help_for(program, path);
+        for sub in &self.subcommands {
+            let path = [path, &[sub.name]].concat();
+            help = help.subcommand(sub.help_tree_for(program, &path));
+        }
+        help
+    }
+    /// Help info for this command, which is reached by the subcommands in `path`.
+    fn help_for(&self, program: &str, path: &[&'static str]) -> HelpInfo {
+        let name = path.last().copied().unwrap_or(program);
+        let mut help = HelpInfo::new(name.to_string(), usage_line(program, &self.usage(path)));
+        if let Some(about) = &self.about {
+            let long = self.long_about.as_ref().unwrap_or(about);
+            help = help.about(HelpText::new(about.clone(), long.clone()));
+        }
+        for example in &self.examples {
+            help = help.example(example.clone());
+        }
+        for (name, description) in &self.env {
+            help = help.env(name.clone(), description.clone());
+        }
+
+        if !self.subcommands.is_empty() {
+            let entries = self
+                .subcommands
+                .iter()
+                .map(|s| {
+                    let about = s.about.clone().unwrap_or_default();
+                    HelpEntry::new(s.name, HelpText::new(about.clone(), about))
+                })
+                .collect();
+            return help.section(HelpSection::new("SUBCOMMANDS", entries));
+        }
+
+        let mut args = Vec::new();
+        let mut flags = Vec::new();
+        for arg in &self.args {
+            match arg.kind {
+                ArgKind::Positional => {
+                    let tags = [(arg.variadic, "[variadic]"), (!arg.required, "[optional]")];
+                    args.push(HelpEntry::new(arg.name, arg.help_text(&tags)));
+                }
+                ArgKind::Named => args.push(HelpEntry::new(arg.help_name(), arg.help_text(&[(!arg.required, "[optional]")]))),
+                ArgKind::Flag => flags.push(HelpEntry::new(arg.help_name(), arg.help_text(&[]))),
+            }
+        }
+        for (heading, entries) in [("ARGUMENTS", args), ("FLAGS", flags)] {
+            if !entries.is_empty() {
+                help = help.section(HelpSection::new(heading, entries));
+            }
+        }
+        help
+    }
+}
+
+///
+/// An argument of a `Command`.
+///
+/// Arguments are required unless `optional` is called; flags are never required.
+#[derive(Clone, Debug)]
+pub struct Arg {
+    kind: ArgKind,
+    name: &'static str,
+    /// The long name of a named argument or flag, such as `--time-out`.
+    long: &'static str,
+    short: Option<&'static str>,
+    help: Option<Cow<'static, str>>,
+    long_help: Option<Cow<'static, str>>,
+    required: bool,
+    variadic: bool,
+}
+
+impl Arg {
+    fn new(kind: ArgKind, name: Cow<'static, str>) -> Self {
+        let name = intern(name);
+        let long = match kind {
+            ArgKind::Positional => "",
+            _ => intern(format!("--{}", name.replace('_', "-")).into()),
+        };
+        Self {
+            kind,
+            name,
+            long,
+            short: None,
+            help: None,
+            long_help: None,
+            required: true,
+            variadic: false,
+        }
+    }
+    /// An argument that's passed by its position, such as `FILE` in `rm FILE`.
+    pub fn positional(name: impl Into<Cow<'static, str>>) -> Self {
+        Self::new(ArgKind::Positional, name.into())
+    }
+    /// An argument that's passed by name, followed by its value, such as `--time-out 30` for `Arg::named("time_out")`.
+    pub fn named(name: impl Into<Cow<'static, str>>) -> Self {
+        Self::new(ArgKind::Named, name.into())
+    }
+    /// A flag, which doesn't take a value, such as `--force`.
+    pub fn flag(name: impl Into<Cow<'static, str>>) -> Self {
+        Self::new(ArgKind::Flag, name.into())
+    }
+    ///
+    /// A short name for a named argument or flag, such as `-t`.
+    ///
+    /// # Panics
+    /// If this is a positional argument.
+    pub fn short(mut self, short: char) -> Self {
+        assert!(self.kind != ArgKind::Positional, "Positional argument `{}` can't have a short name.", self.name);
+        self.short = Some(short_name(short));
+        self
+    }
+    /// A description of the argument, shown by `-h` and `--help`.
+    pub fn help(mut self, help: impl Into<Cow<'static, str>>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+    /// A longer description of the argument, shown by `--help` instead of `help`.
+    pub fn long_help(mut self, help: impl Into<Cow<'static, str>>) -> Self {
+        self.long_help = Some(help.into());
+        self
+    }
+    /// Don't require the argument to be passed.
+    pub fn optional(mut self) -> Self {
+        self.required = false;
+        self
+    }
+    ///
+    /// Accept any number of values for the argument, from the rest of the command line.
+    /// This must be the last positional argument.
+    ///
+    /// # Panics
+    /// If this isn't a positional argument.
+    pub fn variadic(mut self) -> Self {
+        assert!(self.kind == ArgKind::Positional, "Argument `{}` can't be variadic, since it isn't positional.", self.name);
+        self.variadic = true;
+        self
+    }
+
+    ///
+    /// Make sure that a named argument or flag doesn't take the name of `--help` or `--version`,
+    /// which would stop the built-in flag from working. `version` is whether the command accepts `--version`.
+    fn check_builtin(&self, version: bool) {
+        if self.kind == ArgKind::Positional {
+            return;
+        }
+        let taken = match (self.short, self.long) {
+            (Some("-h"), _) => Some("-h"),
+            (_, "--help") => Some("--help"),
+            (Some("-V"), _) if version => Some("-V"),
+            (_, "--version") if version => Some("--version"),
+            _ => None,
+        };
+        if let Some(name) = taken {
+            panic!("Argument `{}` can't use `{}`, which is already used to show help or the version", self.name, name);
+        }
+    }
+    /// The name that this argument shares with `other`, if any.
+    fn clash(&self, other: &Arg) -> Option<&'static str> {
+        if self.name == other.name {
+            Some(self.name)
+        } else if self.kind != ArgKind::Positional && self.long == other.long {
+            Some(self.long)
+        } else if self.short.is_some() && self.short == other.short {
+            self.short
+        } else {
+            None
+        }
+    }
+    /// The name of a named argument or flag in help, including its short form.
+    fn help_name(&self) -> String {
+        match self.short {
+            Some(short) => format!("{}, {}", short, self.long),
+            None => self.long.to_string(),
+        }
+    }
+    /// The argument's help, with the tags that describe it.
+    fn help_text(&self, tags: &[(bool, &'static str)]) -> HelpText {
+        let help = self.help.clone().unwrap_or_default();
+        let long_help = self.long_help.clone().unwrap_or_else(|| help.clone());
+        HelpText::new(help, long_help).tags(tags.iter().filter(|t| t.0).map(|t| t.1))
+    }
+}
+
+///
+/// The arguments passed to a `Command`, from `Command::parse`.
+///
+/// Arguments are looked up by the name they were defined with, such as `time_out` for `Arg::named("time_out")`.
+///
+/// # Panics
+/// Every method that takes the name of an argument panics if the command doesn't have an argument with that name.
+#[derive(Clone, Debug)]
+pub struct Matches {
+    args: Vec<Match>,
+    subcommand: Option<(&'static str, Box<Matches>)>,
+}
+
+#[derive(Clone, Debug)]
+struct Match {
+    name: &'static str,
+    /// The first value of the argument, for errors.
+    arg: ArgRef,
+    values: Vec<OsString>,
+    /// The number of times a flag was passed.
+    occurrences: usize,
+}
+
+impl Matches {
+    fn get(&self, name: &str) -> &Match {
+        self.args
+            .iter()
+            .find(|m| m.name == name)
+            .unwrap_or_else(|| panic!("No argument named `{}`.", name))
+    }
+    /// Whether the argument was passed.
+    pub fn contains(&self, name: &str) -> bool {
+        self.occurrences(name) > 0
+    }
+    /// The number of times a flag was passed, or the number of values passed for any other argument.
+    pub fn occurrences(&self, name: &str) -> usize {
+        let arg = self.get(name);
+        arg.occurrences + arg.values.len()
+    }
+    /// The raw value of an argument, if it was passed. For variadic arguments, this is the first value.
+    pub fn value_os(&self, name: &str) -> Option<&OsStr> {
+        self.get(name).values.first().map(OsString::as_os_str)
+    }
+    /// The raw values of an argument.
+    pub fn values_os(&self, name: &str) -> &[OsString] {
+        &self.get(name).values
+    }
+    ///
+    /// Parse the value of an argument, if it was passed.
+    ///
+    /// Errors are the same as they would be for a field of type `T` in a derived command.
+    pub fn value<T: Argument>(&self, name: &str) -> Result<Option<T>, Error> {
+        let arg = self.get(name);
+        arg.values
+            .first()
+            .map(|val| T::parse(crate::args::into_string(val.clone(), arg.arg)?, arg.arg))
+            .transpose()
+    }
+    /// Parse every value of an argument.
+    pub fn values<T: Argument>(&self, name: &str) -> Result<Vec<T>, Error> {
+        let arg = self.get(name);
+        arg.values
+            .iter()
+            .enumerate()
+            .map(|(i, val)| {
+                let arg_ref = match arg.arg {
+                    ArgRef::Positional(index) => ArgRef::Positional(index + i),
+                    named => named,
+                };
+                T::parse(crate::args::into_string(val.clone(), arg_ref)?, arg_ref)
+            })
+            .collect()
+    }
+    /// The subcommand that was passed, and its arguments, if the command has subcommands.
+    pub fn subcommand(&self) -> Option<(&str, &Matches)> {
+        self.subcommand.as_ref().map(|(name, matches)| (*name, &**matches))
+    }
+}
+
+///
+/// Keep a name for the rest of the program.
+///
+/// Errors refer to arguments by `&'static str`, so names that were built at runtime are leaked.
+/// They're only leaked the first time they're seen, and names that are already `&'static str` are used as they are.
+fn intern(name: Cow<'static, str>) -> &'static str {
+    static NAMES: Mutex<BTreeSet<&'static str>> = Mutex::new(BTreeSet::new());
+    let name = match name {
+        Cow::Borrowed(name) => return name,
+        Cow::Owned(name) => name,
+    };
+    let mut names = NAMES.lock().unwrap_or_else(PoisonError::into_inner);
+    match names.get(name.as_str()) {
+        Some(&interned) => interned,
+        None => {
+            let interned = Box::leak(name.into_boxed_str());
+            names.insert(interned);
+            interned
+        }
+    }
+}
+
+/// The short name `-c`, which doesn't have to be interned if `c` is ASCII.
+fn short_name(c: char) -> &'static str {
+    // `-` followed by each ASCII character.
+    static ASCII: [u8; 256] = {
+        let mut table = [b'-'; 256];
+        let mut i = 0;
+        while i < 128 {
+            table[i * 2 + 1] = i as u8;
+            i += 1;
+        }
+        table
+    };
+    if c.is_ascii() {
+        let i = c as usize;
+        std::str::from_utf8(&ASCII[i * 2..i * 2 + 2]).unwrap()
+    } else {
+        intern(format!("-{}", c).into())
+    }
+}
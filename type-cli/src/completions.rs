@@ -0,0 +1,581 @@
+use crate::engine::{Flag, Layout};
+use crate::{ArgKind, ArgRef, ArgSpec, Argument, CommandSpec, Error, CLI};
+use std::ffi::OsString;
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+///
+/// A shell that completion scripts can be generated for.
+///
+/// `Shell` can be used as an argument, so a program can print its own completions:
+/// ```ignore
+/// #[derive(CLI)]
+/// enum Tool {
+///     Completions(Shell),
+///     ...
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl Shell {
+    const NAMES: &'static [&'static str] = &["bash", "zsh", "fish"];
+}
+
+impl Display for Shell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Shell::Bash => "bash",
+            Shell::Zsh => "zsh",
+            Shell::Fish => "fish",
+        };
+        f.write_str(name)
+    }
+}
+
+/// The error returned when parsing the name of a shell that isn't supported.
+#[derive(Debug, thiserror::Error)]
+#[error("Unknown shell `{0}`, expected `bash`, `zsh` or `fish`")]
+pub struct UnknownShell(String);
+
+impl FromStr for Shell {
+    type Err = UnknownShell;
+    fn from_str(s: &str) -> Result<Self, UnknownShell> {
+        match s {
+            "bash" => Ok(Shell::Bash),
+            "zsh" => Ok(Shell::Zsh),
+            "fish" => Ok(Shell::Fish),
+            _ => Err(UnknownShell(s.to_string())),
+        }
+    }
+}
+
+impl Argument for Shell {
+    fn parse(val: impl AsRef<str>, arg: ArgRef) -> Result<Self, Error> {
+        let val = val.as_ref();
+        val.parse().map_err(|e| Error::parse(arg, val, e))
+    }
+    fn possible_values() -> Option<&'static [&'static str]> {
+        Some(Self::NAMES)
+    }
+}
+
+///
+/// Generate a script that completes the arguments of the command `T` in `shell`.
+///
+/// The script completes subcommands, flags and named arguments, and the values of any argument whose type
+/// lists its `Argument::possible_values`. Other values are completed as file names.
+/// It's registered for the name of the program, from `#[cli(name = "...")]` or the name of the type.
+pub fn generate<T: CLI>(shell: Shell) -> String {
+    let root = T::spec();
+    let mut commands = Vec::new();
+    walk(&root, ident(root.name), &mut commands);
+    match shell {
+        Shell::Bash => bash(root.name, &commands),
+        Shell::Zsh => zsh(root.name, &commands),
+        Shell::Fish => fish(root.name, &commands),
+    }
+}
+
+///
+/// The environment variable that makes `CLI::process` print completions instead of running the program.
+///
+/// It's set to the name of the shell, and the program is called with the words on the command line after `--`:
+/// ```text
+/// TYPE_CLI_COMPLETE=bash git -- checkout ma
+/// ```
+/// The scripts from `generate` do this for arguments with a `#[complete_with = "function"]` attribute.
+pub const COMPLETE_VAR: &str = "TYPE_CLI_COMPLETE";
+
+///
+/// Find the values that the word under the cursor could be completed to, for the command `T`.
+///
+/// `words` are the arguments after the name of the program, ending with the word under the cursor,
+/// which is empty if the cursor comes after a space.
+/// The words before it are used to find the subcommand, and which argument the cursor is on.
+///
+/// Values come from the argument's `#[complete_with = "function"]` attribute, or its `Argument::possible_values`.
+/// Only values that start with the word under the cursor are returned.
+pub fn complete<T: CLI>(words: impl IntoIterator<Item = impl Into<String>>) -> Vec<String> {
+    let words: Vec<String> = words.into_iter().map(Into::into).collect();
+    let (current, before) = match words.split_last() {
+        Some((current, before)) => (current.as_str(), before),
+        None => ("", &[][..]),
+    };
+
+    let root = T::spec();
+    let mut cmd = &root;
+    // How many positional arguments come before the cursor.
+    let mut pos = 0;
+    // The named argument whose value is under the cursor.
+    let mut value_of = None;
+    let mut before = before.iter();
+    while let Some(word) = before.next() {
+        if !cmd.subcommands.is_empty() {
+            // The first word picks the subcommand, and anything else means the program won't run one.
+            match cmd.subcommand(word) {
+                Some(sub) => cmd = sub,
+                None => return Vec::new(),
+            }
+            continue;
+        }
+        if !word.starts_with('-') {
+            pos += 1;
+            continue;
+        }
+        match cmd.flag(word) {
+            // Skip over its value, unless the value is under the cursor.
+            Flag::Named(i) => {
+                if before.next().is_none() {
+                    value_of = cmd.args.iter().filter(|a| a.kind == ArgKind::Named).nth(i);
+                }
+            }
+            // The program will show help or the version, so there's nothing to complete.
+            Flag::Help(_) | Flag::Version => return Vec::new(),
+            Flag::Flag(_) | Flag::Unknown => {}
+        }
+    }
+
+    let candidates = if let Some(arg) = value_of {
+        arg.candidates(current)
+    } else if current.starts_with('-') {
+        cmd.options().iter().flat_map(ArgSpec::names).map(String::from).collect()
+    } else {
+        // A variadic argument takes every position after it.
+        let arg = cmd
+            .positionals()
+            .nth(pos)
+            .or_else(|| cmd.positionals().last().filter(|a| a.variadic));
+        let values = arg.map_or_else(Vec::new, |a| a.candidates(current));
+        cmd.subcommands.iter().map(|s| s.name.to_string()).chain(values).collect()
+    };
+    candidates.into_iter().filter(|c| c.starts_with(current)).collect()
+}
+
+///
+/// The words to complete, if the program was run by a completion script.
+///
+/// `complete_var` is the value of `COMPLETE_VAR`, and `args` are the arguments of the program, starting with its name.
+pub(crate) fn requested<T: CLI>(complete_var: Option<OsString>, args: &[OsString]) -> Option<Vec<String>> {
+    complete_var?;
+    let mut words = args.iter().skip(1).peekable();
+    if words.peek().is_some_and(|w| *w == "--") {
+        words.next();
+    }
+    Some(complete::<T>(words.map(|w| w.to_string_lossy().into_owned())))
+}
+
+impl CommandSpec {
+    /// What `word` is to this command, which is found the same way as when the command is parsed.
+    fn flag(&self, word: &str) -> Flag {
+        let (_, named, flags) = self.layout();
+        let layout = Layout {
+            positionals: &[],
+            named: &named,
+            flags: &flags,
+            version: self.version,
+        };
+        layout.flag(word)
+    }
+}
+
+impl ArgSpec {
+    /// Whether the shell can complete the values of this argument, instead of completing file names.
+    fn has_values(&self) -> bool {
+        self.values.is_some() || self.complete.is_some()
+    }
+    /// The values this argument could take, given the word under the cursor.
+    fn candidates(&self, current: &str) -> Vec<String> {
+        match (self.complete, self.values) {
+            (Some(complete), _) => complete(current),
+            (None, Some(values)) => values.iter().map(|v| v.to_string()).collect(),
+            (None, None) => Vec::new(),
+        }
+    }
+}
+
+///
+/// List every command in the tree under `cmd`, along with a path that identifies it,
+/// such as `cargo__build` for the `build` subcommand of `cargo`.
+fn walk<'a>(cmd: &'a CommandSpec, path: String, out: &mut Vec<(String, &'a CommandSpec)>) {
+    out.push((path.clone(), cmd));
+    for sub in &cmd.subcommands {
+        walk(sub, format!("{}__{}", path, ident(sub.name)), out);
+    }
+}
+
+/// A name that can be used in a shell function's name.
+fn ident(name: &str) -> String {
+    name.replace(|c: char| !c.is_ascii_alphanumeric(), "_")
+}
+
+fn bash(program: &str, commands: &[(String, &CommandSpec)]) -> String {
+    let func = format!("_{}", commands[0].0);
+    let dynamic = format!("_{}_dynamic", commands[0].0);
+    let mut lines = Vec::new();
+    if any_dynamic(commands) {
+        // Ask the program to complete the word under the cursor, one value per line.
+        lines.push(format!("{}() {{", dynamic));
+        lines.push("    local IFS=$'\\n'".to_string());
+        lines.push(format!(
+            r#"    COMPREPLY=($({}=bash "${{COMP_WORDS[0]}}" -- "${{COMP_WORDS[@]:1:COMP_CWORD}}"))"#,
+            COMPLETE_VAR
+        ));
+        lines.push("}".to_string());
+        lines.push(String::new());
+    }
+    lines.extend(vec![
+        format!("{}() {{", func),
+        "    local cur prev cmd pos word i opts".to_string(),
+        "    COMPREPLY=()".to_string(),
+        r#"    cur="${COMP_WORDS[COMP_CWORD]}""#.to_string(),
+        r#"    prev="${COMP_WORDS[COMP_CWORD-1]}""#.to_string(),
+        format!(r#"    cmd="{}""#, commands[0].0),
+        "    pos=0".to_string(),
+        String::new(),
+        "    # Find the subcommand, and how many positional arguments come before the cursor.".to_string(),
+        "    for ((i = 1; i < COMP_CWORD; i++)); do".to_string(),
+        r#"        word="${COMP_WORDS[i]}""#.to_string(),
+        r#"        case "${cmd}:${word}" in"#.to_string(),
+    ]);
+    for (path, cmd) in commands {
+        for sub in &cmd.subcommands {
+            lines.push(format!(
+                r#"            "{}:{}") cmd="{}__{}"; pos=0; continue ;;"#,
+                path,
+                sub.name,
+                path,
+                ident(sub.name)
+            ));
+        }
+        for arg in cmd.options().iter().filter(|a| a.kind == ArgKind::Named) {
+            // Skip over the value of a named argument.
+            let pattern = bash_pattern(path, arg.names());
+            lines.push(format!("            {}) i=$((i + 1)); continue ;;", pattern));
+        }
+    }
+    lines.push("        esac".to_string());
+    lines.push(r#"        [[ "${word}" != -* ]] && pos=$((pos + 1))"#.to_string());
+    lines.push("    done".to_string());
+
+    // Complete the value of a named argument.
+    let mut values = Vec::new();
+    for (path, cmd) in commands {
+        for arg in cmd.options().iter().filter(|a| a.kind == ArgKind::Named) {
+            let pattern = bash_pattern(path, arg.names());
+            match (arg.complete, arg.values) {
+                (Some(_), _) => values.push(format!("        {}) {}; return 0 ;;", pattern, dynamic)),
+                (None, Some(vals)) => values.push(format!(
+                    r#"        {}) COMPREPLY=($(compgen -W "{}" -- "${{cur}}")); return 0 ;;"#,
+                    pattern,
+                    bash_words(vals)
+                )),
+                // Fall back to completing file names.
+                (None, None) => values.push(format!("        {}) return 0 ;;", pattern)),
+            }
+        }
+    }
+    if !values.is_empty() {
+        lines.push(String::new());
+        lines.push(r#"    case "${cmd}:${prev}" in"#.to_string());
+        lines.append(&mut values);
+        lines.push("    esac".to_string());
+    }
+
+    lines.push(String::new());
+    lines.push(r#"    case "${cmd}" in"#.to_string());
+    for (path, cmd) in commands {
+        let words: Vec<_> = cmd
+            .subcommands
+            .iter()
+            .map(|s| s.name)
+            .chain(cmd.options().iter().flat_map(ArgSpec::names))
+            .collect();
+        lines.push(format!(r#"        {}) opts="{}" ;;"#, path, bash_words(&words)));
+    }
+    lines.push("    esac".to_string());
+
+    // Complete the values of positional arguments.
+    let mut values = Vec::new();
+    for (path, cmd) in commands {
+        if !cmd.positionals().any(ArgSpec::has_values) {
+            continue;
+        }
+        for (i, arg) in cmd.positionals().enumerate() {
+            // A variadic argument takes every position after it, so it comes last.
+            let pattern = if arg.variadic {
+                format!(r#""{}:"*"#, path)
+            } else {
+                format!(r#""{}:{}""#, path, i)
+            };
+            match (arg.complete, arg.values) {
+                (Some(_), _) => values.push(format!(
+                    r#"        {}) if [[ "${{cur}}" != -* ]]; then {}; return 0; fi ;;"#,
+                    pattern, dynamic
+                )),
+                (None, Some(vals)) => values.push(format!(
+                    r#"        {}) opts="${{opts}} {}" ;;"#,
+                    pattern,
+                    bash_words(vals)
+                )),
+                (None, None) => values.push(format!("        {}) ;;", pattern)),
+            }
+        }
+    }
+    if !values.is_empty() {
+        lines.push(r#"    case "${cmd}:${pos}" in"#.to_string());
+        lines.append(&mut values);
+        lines.push("    esac".to_string());
+    }
+
+    lines.push(r#"    COMPREPLY=($(compgen -W "${opts}" -- "${cur}"))"#.to_string());
+    lines.push("}".to_string());
+    lines.push(String::new());
+    lines.push(format!("complete -F {} -o bashdefault -o default {}", func, program));
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+/// Whether any argument in the tree is completed by the program itself, with `#[complete_with]`.
+fn any_dynamic(commands: &[(String, &CommandSpec)]) -> bool {
+    commands
+        .iter()
+        .any(|(_, cmd)| cmd.args.iter().any(|a| a.complete.is_some()))
+}
+
+/// A `case` pattern that matches any of `names` after the command at `path`.
+fn bash_pattern(path: &str, names: impl Iterator<Item = &'static str>) -> String {
+    names
+        .map(|name| format!(r#""{}:{}""#, path, name))
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+/// A list of words for `compgen -W`, escaped for a double-quoted string.
+fn bash_words(words: &[&str]) -> String {
+    let escape = |word: &&str| {
+        word.chars().fold(String::new(), |mut out, c| {
+            if matches!(c, '"' | '\\' | '$' | '`') {
+                out.push('\\');
+            }
+            out.push(c);
+            out
+        })
+    };
+    words.iter().map(escape).collect::<Vec<_>>().join(" ")
+}
+
+fn zsh(program: &str, commands: &[(String, &CommandSpec)]) -> String {
+    let func = format!("_{}", commands[0].0);
+    let dynamic = format!("_{}_dynamic", commands[0].0);
+    // The words on the command line, saved before `_arguments` removes the ones that come before a subcommand.
+    let words = format!("_{}_words", commands[0].0);
+    let mut lines = vec![format!("#compdef {}", program), String::new()];
+    let any_dynamic = any_dynamic(commands);
+    if any_dynamic {
+        // Ask the program to complete the word under the cursor, one value per line.
+        lines.push(format!("{}() {{", dynamic));
+        lines.push("    local -a values".to_string());
+        lines.push(format!(
+            r#"    values=("${{(@f)$({}=zsh ${{{words}[1]}} -- "${{(@){words}[2,-1]}}")}}")"#,
+            COMPLETE_VAR,
+            words = words
+        ));
+        lines.push("    compadd -a values".to_string());
+        lines.push("}".to_string());
+        lines.push(String::new());
+    }
+    for (i, (path, cmd)) in commands.iter().enumerate() {
+        lines.push(format!("_{}() {{", path));
+        if any_dynamic && i == 0 {
+            lines.push(format!("    local -a {}", words));
+            lines.push(format!(r#"    {}=("${{(@)words[1,CURRENT]}}")"#, words));
+        }
+        let mut specs: Vec<String> = cmd.options().iter().map(|a| zsh_option(a, &dynamic)).collect();
+        if cmd.subcommands.is_empty() {
+            specs.extend(cmd.positionals().map(|a| zsh_positional(a, &dynamic)));
+            lines.push("    _arguments -s \\".to_string());
+        } else {
+            specs.push("'1: :->command'".to_string());
+            specs.push("'*:: :->args'".to_string());
+            lines.push("    local line state".to_string());
+            lines.push("    _arguments -s -C \\".to_string());
+        }
+        let last = specs.len() - 1;
+        for (i, spec) in specs.iter().enumerate() {
+            let cont = if i == last { "" } else { " \\" };
+            lines.push(format!("        {}{}", spec, cont));
+        }
+        if !cmd.subcommands.is_empty() {
+            lines.push("    case $state in".to_string());
+            lines.push("        command)".to_string());
+            lines.push("            local commands=(".to_string());
+            for sub in &cmd.subcommands {
+                lines.push(format!("                {}", zsh_quote(&format!("{}:{}", sub.name, sub.about))));
+            }
+            lines.push("            )".to_string());
+            lines.push("            _describe -t commands 'command' commands".to_string());
+            lines.push("            ;;".to_string());
+            lines.push("        args)".to_string());
+            lines.push("            case $line[1] in".to_string());
+            for sub in &cmd.subcommands {
+                lines.push(format!("                {}) _{}__{} ;;", sub.name, path, ident(sub.name)));
+            }
+            lines.push("            esac".to_string());
+            lines.push("            ;;".to_string());
+            lines.push("    esac".to_string());
+        }
+        lines.push("}".to_string());
+        lines.push(String::new());
+    }
+    lines.push(format!(r#"if [ "$funcstack[1]" = "{}" ]; then"#, func));
+    lines.push(format!(r#"    {} "$@""#, func));
+    lines.push("else".to_string());
+    lines.push(format!("    compdef {} {}", func, program));
+    lines.push("fi".to_string());
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+/// An `_arguments` spec for a named argument or flag, such as `'(-v --verbose)'{-v,--verbose}'[Print more]'`.
+fn zsh_option(arg: &ArgSpec, dynamic: &str) -> String {
+    let names: Vec<_> = arg.names().collect();
+    let mut spec = if names.len() > 1 {
+        format!("'({})'{{{}}}'", names.join(" "), names.join(","))
+    } else {
+        format!("'{}", names[0])
+    };
+    spec.push_str(&format!("[{}]", zsh_escape(arg.help)));
+    if arg.kind == ArgKind::Named {
+        spec.push_str(&format!(":{}:{}", arg.name, zsh_action(arg, dynamic)));
+    }
+    spec.push('\'');
+    spec
+}
+
+/// An `_arguments` spec for a positional argument, such as `':file:_files'`.
+fn zsh_positional(arg: &ArgSpec, dynamic: &str) -> String {
+    let prefix = if arg.variadic {
+        "*:"
+    } else if arg.required {
+        ":"
+    } else {
+        "::"
+    };
+    let message = if arg.help.is_empty() { arg.name } else { arg.help };
+    format!("'{}{}:{}'", prefix, zsh_escape(message), zsh_action(arg, dynamic))
+}
+
+///
+/// How zsh completes the value of an argument: by calling the `dynamic` function to ask the program,
+/// with one of its possible values, or with a file name.
+fn zsh_action(arg: &ArgSpec, dynamic: &str) -> String {
+    match (arg.complete, arg.values) {
+        (Some(_), _) => dynamic.to_string(),
+        (None, Some(vals)) => {
+            // Values in the list are separated by spaces.
+            let vals: Vec<_> = vals.iter().map(|v| zsh_escape(v).replace(' ', r"\ ")).collect();
+            format!("({})", vals.join(" "))
+        }
+        (None, None) => "_files".to_string(),
+    }
+}
+
+/// Escape text to go inside a single-quoted `_arguments` spec.
+fn zsh_escape(text: &str) -> String {
+    text.chars().fold(String::new(), |mut out, c| {
+        match c {
+            '\'' => out.push_str(r"'\''"),
+            '[' | ']' | ':' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+        out
+    })
+}
+
+/// Quote text as a single-quoted shell word.
+fn zsh_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+fn fish(program: &str, commands: &[(String, &CommandSpec)]) -> String {
+    // Ask the program to complete the token under the cursor.
+    let dynamic = format!(
+        "(env {}=fish {} -- (commandline -opc)[2..-1] (commandline -ct))",
+        COMPLETE_VAR, program
+    );
+    let mut lines = Vec::new();
+    for (i, (_, cmd)) in commands.iter().enumerate() {
+        // The root command, subcommands of the root, or deeper subcommands.
+        let condition = if i == 0 {
+            if cmd.subcommands.is_empty() {
+                String::new()
+            } else {
+                " -n __fish_use_subcommand".to_string()
+            }
+        } else {
+            format!(" -n '__fish_seen_subcommand_from {}'", cmd.name)
+        };
+        let complete = format!("complete -c {}{}", program, condition);
+        for sub in &cmd.subcommands {
+            lines.push(format!("{} -f -a {}{}", complete, sub.name, fish_description(sub.about)));
+        }
+        for arg in cmd.options() {
+            let mut line = complete.clone();
+            if let Some(short) = arg.short {
+                let short = short.trim_start_matches('-');
+                // Fish calls multi-character short names "old-style" options.
+                let flag = if short.chars().count() == 1 { "-s" } else { "-o" };
+                line.push_str(&format!(" {} {}", flag, short));
+            }
+            if let Some(long) = arg.long {
+                line.push_str(&format!(" -l {}", long.trim_start_matches('-')));
+            }
+            if arg.kind == ArgKind::Named {
+                match fish_values(&arg, &dynamic) {
+                    Some(vals) => line.push_str(&format!(" -x -a {}", vals)),
+                    None => line.push_str(" -r"),
+                }
+            }
+            line.push_str(&fish_description(arg.help));
+            lines.push(line);
+        }
+        for arg in cmd.positionals() {
+            if let Some(vals) = fish_values(arg, &dynamic) {
+                lines.push(format!("{} -f -a {}{}", complete, vals, fish_description(arg.help)));
+            }
+        }
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+/// The values fish should complete for an argument, by running `dynamic` or from its possible values.
+fn fish_values(arg: &ArgSpec, dynamic: &str) -> Option<String> {
+    match (arg.complete, arg.values) {
+        (Some(_), _) => Some(fish_quote(dynamic)),
+        (None, Some(vals)) => Some(fish_quote(&vals.join(" "))),
+        (None, None) => None,
+    }
+}
+
+/// The `-d` option describing a completion, if there is a description.
+fn fish_description(help: &str) -> String {
+    if help.is_empty() {
+        String::new()
+    } else {
+        format!(" -d {}", fish_quote(help))
+    }
+}
+
+/// Quote text as a single-quoted fish string.
+fn fish_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\\', r"\\").replace('\'', r"\'"))
+}
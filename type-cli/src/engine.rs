@@ -0,0 +1,198 @@
+use crate::{Error, ErrorCollector, Errors, HelpKind, RawArg};
+use std::ffi::OsString;
+use std::iter::Peekable;
+
+///
+/// How the arguments of a command are laid out, which is all the parser needs to know
+/// to split a command line into the values of each argument.
+///
+/// `#[derive(CLI)]` emits this as a constant for each struct with named fields, and `Command` builds one when it's parsed.
+#[derive(Clone, Copy, Debug)]
+pub struct Layout<'a> {
+    pub positionals: &'a [Positional],
+    pub named: &'a [Name],
+    pub flags: &'a [Name],
+    /// Whether the command accepts `--version`.
+    pub version: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Positional {
+    pub required: bool,
+    pub variadic: bool,
+}
+
+/// The names of a named argument or flag, such as `--time-out` and `-t`.
+#[derive(Clone, Copy, Debug)]
+pub struct Name {
+    pub long: &'static str,
+    pub short: Option<&'static str>,
+}
+
+/// What was found on the command line.
+pub enum Scan<A> {
+    /// The values of the arguments were stored in the `Tokens`.
+    /// `extra` is any arguments after the last positional argument, which the command doesn't accept.
+    Args { extra: Vec<A> },
+    /// The user asked for help, or help should be shown because no arguments were passed.
+    Help { requested: bool, kind: HelpKind },
+    Version,
+}
+
+///
+/// Where the values passed for each argument are stored, in the same order as the `Layout`.
+///
+/// These haven't been parsed, so nothing has been checked except that every required positional argument was passed.
+/// The caller provides the storage, so that code generated by `#[derive(CLI)]` can keep it on the stack.
+#[derive(Debug)]
+pub struct Tokens<'t, A> {
+    /// The value of each positional argument that isn't variadic.
+    pub positionals: &'t mut [Option<A>],
+    /// The values of the variadic argument, if there is one.
+    pub variadic: &'t mut Vec<A>,
+    /// The value of each named argument, if it was passed. If it was passed more than once, this is the last value.
+    pub named: &'t mut [Option<A>],
+    /// The number of times each flag was passed.
+    pub flags: &'t mut [usize],
+}
+
+///
+/// Split `args` into the values of each argument of a command, storing them in `tokens`.
+///
+/// Named arguments and flags can come before, after or between positional arguments.
+/// Anything that starts with `-` is read as a flag, and the word after a named argument is its value.
+/// If the command has required positional arguments, and nothing was passed, help is shown.
+pub fn scan<'a, A: RawArg<'a>>(
+    layout: Layout<'_>,
+    args: impl Iterator<Item = A>,
+    mut tokens: Tokens<'_, A>,
+    errors: &mut ErrorCollector,
+) -> Result<Scan<A>, Errors> {
+    let mut args = args.peekable();
+
+    // Read any flags at the front of `args`, returning early if help or the version should be shown.
+    macro_rules! flags {
+        () => {
+            if let Some(scan) = flags(layout, &mut args, &mut tokens, errors)? {
+                return Ok(scan);
+            }
+        };
+    }
+
+    if args.peek().is_none() && layout.positionals.iter().any(|p| p.required && !p.variadic) {
+        return Ok(Scan::Help {
+            requested: false,
+            kind: HelpKind::Short,
+        });
+    }
+    flags!();
+    for (i, positional) in layout.positionals.iter().enumerate() {
+        if positional.variadic {
+            while let Some(arg) = args.next() {
+                tokens.variadic.push(arg);
+                flags!();
+            }
+        } else if positional.required {
+            match args.next() {
+                Some(arg) => tokens.positionals[i] = Some(arg),
+                None => errors.push(Error::expected_positional(i + 1))?,
+            }
+            flags!();
+        } else if let Some(arg) = args.next() {
+            tokens.positionals[i] = Some(arg);
+            flags!();
+        }
+    }
+    Ok(Scan::Args { extra: args.collect() })
+}
+
+///
+/// The value that `scan` stores for the named argument called `long`, if the command has one.
+///
+/// Nothing is parsed or reported, but errors stop the scan where they would stop the parser,
+/// so this is the value the parser sees. `CLI::process` uses it to find `--color` when help or an error is shown.
+pub(crate) fn scan_named(layout: Layout<'_>, args: impl Iterator<Item = OsString>, long: &str) -> Option<OsString> {
+    let index = layout.named.iter().position(|n| n.long == long)?;
+    let mut positionals = vec![None; layout.positionals.len()];
+    let mut variadic = Vec::new();
+    let mut named = vec![None; layout.named.len()];
+    let mut flags = vec![0; layout.flags.len()];
+    let tokens = Tokens {
+        positionals: &mut positionals,
+        variadic: &mut variadic,
+        named: &mut named,
+        flags: &mut flags,
+    };
+    // Errors are reported when the command is parsed.
+    let _ = scan(layout, args, tokens, &mut ErrorCollector::first());
+    named.swap_remove(index)
+}
+
+/// What a word that starts with `-` is, to a command with a given `Layout`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Flag {
+    /// The named argument at this index of `Layout::named`. The next word is its value.
+    Named(usize),
+    /// The flag at this index of `Layout::flags`.
+    Flag(usize),
+    Version,
+    Help(HelpKind),
+    Unknown,
+}
+
+/// Read named arguments and flags until the next word that doesn't start with `-`.
+fn flags<'a, A: RawArg<'a>>(
+    layout: Layout<'_>,
+    args: &mut Peekable<impl Iterator<Item = A>>,
+    tokens: &mut Tokens<'_, A>,
+    errors: &mut ErrorCollector,
+) -> Result<Option<Scan<A>>, Errors> {
+    while let Some(arg) = args.next_if(|a| a.as_os_str().to_string_lossy().starts_with('-')) {
+        let arg = arg.as_os_str().to_string_lossy();
+        match layout.flag(&arg) {
+            Flag::Named(i) => match args.next() {
+                Some(val) => tokens.named[i] = Some(val),
+                None => errors.push(Error::expected_value(layout.named[i].long))?,
+            },
+            Flag::Flag(i) => tokens.flags[i] += 1,
+            Flag::Version => return Ok(Some(Scan::Version)),
+            Flag::Help(kind) => return Ok(Some(Scan::Help { requested: true, kind })),
+            Flag::Unknown => errors.push(Error::unknown_flag(arg.into_owned(), &layout.known_flags()))?,
+        }
+    }
+    Ok(None)
+}
+
+impl Layout<'_> {
+    /// Find out what `arg` is, given that it starts with `-`.
+    pub fn flag(&self, arg: &str) -> Flag {
+        let is_name = |name: &Name| name.long == arg || name.short == Some(arg);
+        if let Some(i) = self.named.iter().position(is_name) {
+            Flag::Named(i)
+        } else if let Some(i) = self.flags.iter().position(is_name) {
+            Flag::Flag(i)
+        } else if self.version && (arg == "--version" || arg == "-V") {
+            Flag::Version
+        } else if arg == "--help" {
+            Flag::Help(HelpKind::Long)
+        } else if arg == "-h" {
+            Flag::Help(HelpKind::Short)
+        } else {
+            Flag::Unknown
+        }
+    }
+    /// Every flag that can be passed to this command, for suggesting corrections to typos.
+    fn known_flags(&self) -> Vec<&'static str> {
+        let mut known: Vec<&'static str> = self
+            .named
+            .iter()
+            .chain(self.flags)
+            .flat_map(|n| std::iter::once(n.long).chain(n.short))
+            .collect();
+        known.extend(&["--help", "-h"]);
+        if self.version {
+            known.extend(&["--version", "-V"]);
+        }
+        known
+    }
+}
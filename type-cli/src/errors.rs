@@ -0,0 +1,449 @@
+use std::borrow::Cow;
+use std::error::Error as StdError;
+use std::ffi::OsStr;
+use std::fmt::{self, Display};
+use std::iter::FromIterator;
+
+///
+/// An error that occurred while parsing a command.
+///
+/// Use `kind` to find out what went wrong,
+/// and `arg`, `token` or `command_path` to find out where.
+#[derive(Debug)]
+pub struct Error {
+    // Boxed so that `Result<T, Error>` stays small.
+    repr: Box<Repr>,
+}
+
+#[derive(Debug)]
+struct Repr {
+    kind: ErrorKind,
+    arg: Option<ArgRef>,
+    token: Option<String>,
+    suggestion: Option<&'static str>,
+    command_path: Vec<&'static str>,
+    program: Option<String>,
+    usage: Option<String>,
+    source: Option<Box<dyn StdError + Send + Sync>>,
+}
+
+/// The kind of error that occurred while parsing a command.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// A required named argument was not passed.
+    ExpectedNamed,
+    /// A required positional argument was not passed.
+    ExpectedPositional,
+    /// A named argument was passed without a value after it.
+    ExpectedValue,
+    /// A flag or named argument that the command doesn't have.
+    UnknownFlag,
+    /// More positional arguments than the command accepts.
+    ExtraArg,
+    /// A subcommand that doesn't exist.
+    UnknownSub,
+    /// An argument's value could not be parsed.
+    Parse,
+    /// An argument's value is not valid unicode, but its type needs a `str`.
+    InvalidUnicode,
+}
+
+impl Error {
+    fn new(kind: ErrorKind) -> Self {
+        let repr = Repr {
+            kind,
+            arg: None,
+            token: None,
+            suggestion: None,
+            command_path: Vec::new(),
+            program: None,
+            usage: None,
+            source: None,
+        };
+        Self { repr: Box::new(repr) }
+    }
+    fn with_arg(mut self, arg: ArgRef) -> Self {
+        self.repr.arg = Some(arg);
+        self
+    }
+    fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.repr.token = Some(token.into());
+        self
+    }
+
+    ///
+    /// Create an error for a value that could not be parsed.
+    ///
+    /// This is meant to be used in implementations of `Argument`.
+    pub fn parse(
+        arg: ArgRef,
+        val: impl Into<String>,
+        err: impl Into<Box<dyn StdError + Send + Sync>>,
+    ) -> Self {
+        let mut e = Self::new(ErrorKind::Parse).with_arg(arg).with_token(val);
+        e.repr.source = Some(err.into());
+        e
+    }
+    #[doc(hidden)]
+    pub fn invalid_unicode(arg: ArgRef, val: &OsStr) -> Self {
+        Self::new(ErrorKind::InvalidUnicode)
+            .with_arg(arg)
+            .with_token(val.to_string_lossy())
+    }
+    #[doc(hidden)]
+    pub fn expected_named(name: &'static str) -> Self {
+        Self::new(ErrorKind::ExpectedNamed).with_arg(ArgRef::Named(name))
+    }
+    #[doc(hidden)]
+    pub fn expected_positional(index: usize) -> Self {
+        Self::new(ErrorKind::ExpectedPositional).with_arg(ArgRef::Positional(index))
+    }
+    #[doc(hidden)]
+    pub fn expected_value(name: &'static str) -> Self {
+        Self::new(ErrorKind::ExpectedValue).with_arg(ArgRef::Named(name))
+    }
+    #[doc(hidden)]
+    pub fn extra_arg(token: impl Into<String>) -> Self {
+        Self::new(ErrorKind::ExtraArg).with_token(token)
+    }
+    /// Create an `UnknownFlag` error, suggesting the closest of the `known` flags.
+    #[doc(hidden)]
+    pub fn unknown_flag(flag: impl Into<String>, known: &[&'static str]) -> Self {
+        let flag = flag.into();
+        let mut e = Self::new(ErrorKind::UnknownFlag);
+        e.repr.suggestion = crate::suggest::suggest(&flag, known);
+        e.with_token(flag)
+    }
+    /// Create an `UnknownSub` error, suggesting the closest of the `known` subcommands.
+    #[doc(hidden)]
+    pub fn unknown_sub(sub: impl Into<String>, known: &[&'static str]) -> Self {
+        let sub = sub.into();
+        let mut e = Self::new(ErrorKind::UnknownSub);
+        e.repr.suggestion = crate::suggest::suggest(&sub, known);
+        e.with_token(sub)
+    }
+
+    /// What went wrong.
+    pub fn kind(&self) -> ErrorKind {
+        self.repr.kind
+    }
+    /// The argument that caused the error, if it was caused by a specific argument.
+    pub fn arg(&self) -> Option<ArgRef> {
+        self.repr.arg
+    }
+    ///
+    /// The command-line token that caused the error:
+    /// the unknown flag or subcommand, the extra argument, or the value that failed to parse.
+    ///
+    /// Tokens that aren't valid unicode are converted lossily.
+    pub fn token(&self) -> Option<&str> {
+        self.repr.token.as_deref()
+    }
+    /// For an unknown flag or subcommand, the known one that is closest to what was typed.
+    pub fn suggestion(&self) -> Option<&'static str> {
+        self.repr.suggestion
+    }
+    ///
+    /// The subcommands leading to the command where the error occurred.
+    ///
+    /// This is empty if the error occurred in the top-level command.
+    pub fn command_path(&self) -> &[&'static str] {
+        &self.repr.command_path
+    }
+    ///
+    /// The usage line of the command where the error occurred, such as `grep [OPTIONS] <PATTERN>`.
+    ///
+    /// This is only set for errors caused by passing the wrong arguments,
+    /// not for values that failed to parse.
+    pub fn usage(&self) -> Option<&str> {
+        self.repr.usage.as_deref()
+    }
+    ///
+    /// The name of the program that the error occurred in.
+    ///
+    /// `CLI::process` shows this before the error, like `fsctl: Unknown flag`.
+    pub fn program(&self) -> Option<&str> {
+        self.repr.program.as_deref()
+    }
+}
+
+impl ErrorKind {
+    /// Whether this error means that the command was called with the wrong shape of arguments.
+    fn is_usage(self) -> bool {
+        !matches!(self, ErrorKind::Parse | ErrorKind::InvalidUnicode)
+    }
+}
+
+/// Usage errors are followed by the usage line of the command.
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_message(f)?;
+        match &self.repr.usage {
+            Some(usage) => write!(f, "\n\nUsage: {}", usage),
+            None => Ok(()),
+        }
+    }
+}
+impl Error {
+    fn fmt_message(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let arg = self.repr.arg.as_ref().map(ToString::to_string).unwrap_or_default();
+        let token = self.token().unwrap_or_default();
+        match (self.repr.kind, self.repr.arg) {
+            (ErrorKind::ExpectedNamed, Some(ArgRef::Named(name))) => {
+                write!(f, "Expected an argument named `{}`", name)
+            }
+            (ErrorKind::ExpectedPositional, Some(ArgRef::Positional(index))) => {
+                write!(f, "Expected an argument at position `{}`", index)
+            }
+            (ErrorKind::ExpectedValue, Some(ArgRef::Named(name))) => {
+                write!(f, "Expected a value after argument `{}`", name)
+            }
+            (ErrorKind::UnknownFlag, _) => {
+                write!(f, "Unknown flag `{}`", token)?;
+                self.fmt_suggestion(f)
+            }
+            (ErrorKind::ExtraArg, _) => write!(f, "Unexpected positional argument `{}`", token),
+            (ErrorKind::UnknownSub, _) => {
+                write!(f, "Unknown subcommand `{}`", token)?;
+                self.fmt_suggestion(f)
+            }
+            (ErrorKind::Parse, _) => {
+                write!(f, "Error parsing {}:", arg)?;
+                if let Some(source) = &self.repr.source {
+                    write!(f, "\n{}", source)?;
+                }
+                Ok(())
+            }
+            (ErrorKind::InvalidUnicode, _) => write!(f, "Invalid unicode in {}: `{}`", arg, token),
+            (kind, _) => write!(f, "{:?} {}", kind, arg),
+        }
+    }
+    fn fmt_suggestion(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.repr.suggestion {
+            Some(s) => write!(f, ", did you mean `{}`?", s),
+            None => Ok(()),
+        }
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match &self.repr.source {
+            Some(source) => Some(&**source),
+            None => None,
+        }
+    }
+}
+
+/// A way to refer to an argument in an error.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ArgRef {
+    Positional(usize),
+    Named(&'static str),
+}
+impl Display for ArgRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArgRef::Positional(index) => {
+                write!(f, "positional argument `{}`", index)
+            }
+            ArgRef::Named(name) => {
+                write!(f, "argument `{}`", name)
+            }
+        }
+    }
+}
+
+///
+/// Every error that occurred while parsing a command, from `CLI::parse_all_errors`.
+///
+/// This always contains at least one error.
+#[derive(Debug)]
+pub struct Errors(Vec<Error>);
+
+impl Errors {
+    /// The number of errors.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+    /// This is always `false`, but is included for consistency with `len`.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    pub fn iter(&self) -> std::slice::Iter<'_, Error> {
+        self.0.iter()
+    }
+    /// The first error that occurred.
+    pub fn first(&self) -> &Error {
+        &self.0[0]
+    }
+    /// The first error that occurred.
+    pub fn into_first(self) -> Error {
+        self.0.into_iter().next().expect("`Errors` is never empty")
+    }
+}
+
+impl IntoIterator for Errors {
+    type Item = Error;
+    type IntoIter = std::vec::IntoIter<Error>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+impl<'a> IntoIterator for &'a Errors {
+    type Item = &'a Error;
+    type IntoIter = std::slice::Iter<'a, Error>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl From<Error> for Errors {
+    fn from(e: Error) -> Self {
+        Errors(vec![e])
+    }
+}
+
+///
+/// A single error is shown on its own; several are shown as a list.
+/// The usage line is shown once at the end, if any of the errors are usage errors.
+impl Display for Errors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let [e] = self.0.as_slice() {
+            return write!(f, "{}", e);
+        }
+        /// Shows an error without its usage line.
+        struct Message<'a>(&'a Error);
+        impl Display for Message<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.fmt_message(f)
+            }
+        }
+        write!(f, "Found {} errors:", self.0.len())?;
+        for e in &self.0 {
+            // Indent any extra lines so they stay under their list item.
+            let e = Message(e).to_string().replace('\n', "\n    ");
+            write!(f, "\n  - {}", e)?;
+        }
+        match self.0.iter().find_map(Error::usage) {
+            Some(usage) => write!(f, "\n\nUsage: {}", usage),
+            None => Ok(()),
+        }
+    }
+}
+impl StdError for Errors {}
+
+///
+/// Decides whether the code generated by `#[derive(CLI)]` stops on the first error,
+/// or keeps going to collect every error.
+#[doc(hidden)]
+pub struct ErrorCollector {
+    all: bool,
+    errors: Vec<Error>,
+    command_path: Vec<&'static str>,
+    program: Cow<'static, str>,
+    /// The usage line of the current command, after the program name.
+    usage: Option<Cow<'static, str>>,
+}
+
+impl ErrorCollector {
+    /// Stop parsing on the first error.
+    pub fn first() -> Self {
+        Self::new(false)
+    }
+    /// Keep parsing after errors, and report all of them.
+    pub fn all() -> Self {
+        Self::new(true)
+    }
+    fn new(all: bool) -> Self {
+        Self {
+            all,
+            errors: Vec::new(),
+            command_path: Vec::new(),
+            program: Cow::Borrowed(""),
+            usage: None,
+        }
+    }
+
+    /// Any errors from now on will have occurred in the subcommand `name`.
+    pub fn enter(&mut self, name: &'static str) {
+        self.command_path.push(name);
+    }
+    /// Set the name of the program.
+    pub fn set_program(&mut self, program: &'static str) {
+        self.program = Cow::Borrowed(program);
+    }
+    /// Use the file name of `argv0` as the name of the program, or `default` if it doesn't have one.
+    pub fn set_program_from_argv0(&mut self, argv0: Option<&OsStr>, default: &'static str) {
+        let name = argv0
+            .and_then(|a| std::path::Path::new(a).file_name())
+            .map(|n| n.to_string_lossy().into_owned());
+        self.program = match name {
+            Some(name) if !name.is_empty() => Cow::Owned(name),
+            _ => Cow::Borrowed(default),
+        };
+    }
+    /// The name of the program.
+    pub fn program(&self) -> &str {
+        &self.program
+    }
+    /// Usage errors from now on will show this usage line, which goes after the name of the program.
+    pub fn set_usage(&mut self, usage: impl Into<Cow<'static, str>>) {
+        self.usage = Some(usage.into());
+    }
+
+    /// Record an error.
+    /// Returns `Err` if parsing should stop immediately.
+    pub fn push(&mut self, mut e: Error) -> Result<(), Errors> {
+        e.repr.command_path = self.command_path.clone();
+        e.repr.program = Some(self.program.to_string());
+        if e.repr.kind.is_usage() {
+            e.repr.usage = self.usage.as_ref().map(|usage| crate::help::usage_line(&self.program, usage));
+        }
+        if self.all {
+            self.errors.push(e);
+            Ok(())
+        } else {
+            Err(e.into())
+        }
+    }
+    /// Record an error that parsing can't continue after, returning every recorded error.
+    pub fn fatal(&mut self, e: Error) -> Errors {
+        match self.push(e) {
+            Ok(()) => Errors(std::mem::take(&mut self.errors)),
+            Err(e) => e,
+        }
+    }
+    /// Record the error from a result, if there is one.
+    pub fn check<T>(&mut self, res: Result<T, Error>) -> Result<Option<T>, Errors> {
+        match res {
+            Ok(val) => Ok(Some(val)),
+            Err(e) => self.push(e).map(|_| None),
+        }
+    }
+    /// Record every error in a sequence of results,
+    /// collecting the values only if there were no errors.
+    pub fn collect<T, C: FromIterator<T>>(
+        &mut self,
+        vals: impl IntoIterator<Item = Result<T, Error>>,
+    ) -> Result<Option<C>, Errors> {
+        let mut ok = Vec::new();
+        let mut any_err = false;
+        for val in vals {
+            match self.check(val)? {
+                Some(val) => ok.push(val),
+                None => any_err = true,
+            }
+        }
+        Ok(if any_err { None } else { Some(ok.into_iter().collect()) })
+    }
+    /// Returns every recorded error, if there were any.
+    pub fn finish(self) -> Result<(), Errors> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Errors(self.errors))
+        }
+    }
+}
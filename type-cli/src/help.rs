@@ -0,0 +1,528 @@
+use crate::style::Style;
+use std::borrow::Cow;
+use std::fmt::{self, Display};
+
+///
+/// Help info about a command.
+///
+/// Help starts with a usage line, such as `Usage: grep [OPTIONS] <PATTERN> [FILE]...`,
+/// followed by a description of the command, sections listing its arguments or subcommands, the environment variables
+/// it reads and examples of using it (which are only shown by `--help`), and an optional footer.
+/// Each part can be changed before the help is shown.
+///
+/// When displayed, the help is laid out to fit the width of the terminal,
+/// which is read from the `COLUMNS` environment variable.
+/// Use `HelpInfo::render` or a `HelpRenderer` to lay it out yourself.
+#[derive(Clone, Debug)]
+pub struct HelpInfo {
+    name: Cow<'static, str>,
+    usage: Cow<'static, str>,
+    about: Option<HelpText>,
+    sections: Vec<HelpSection>,
+    subcommands: Vec<HelpInfo>,
+    examples: Vec<Cow<'static, str>>,
+    env: Vec<(Cow<'static, str>, Cow<'static, str>)>,
+    footer: Option<Cow<'static, str>>,
+    requested: bool,
+    kind: HelpKind,
+}
+
+///
+/// Which rendering of the help to show.
+///
+/// `-h` shows a short summary, and `--help` shows the long descriptions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HelpKind {
+    /// A compact summary, with one line for each argument.
+    Short,
+    /// The full description of the command and each of its arguments.
+    Long,
+}
+
+impl HelpInfo {
+    ///
+    /// Create help for the command `name` with the given usage line, such as `grep [OPTIONS] <PATTERN>`.
+    ///
+    /// The help is long, as if the user had passed `--help`.
+    pub fn new(name: impl Into<Cow<'static, str>>, usage: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            name: name.into(),
+            usage: usage.into(),
+            about: None,
+            sections: Vec::new(),
+            subcommands: Vec::new(),
+            examples: Vec::new(),
+            env: Vec::new(),
+            footer: None,
+            requested: true,
+            kind: HelpKind::Long,
+        }
+    }
+    /// Set the description of the command, shown under the usage line.
+    pub fn about(mut self, about: HelpText) -> Self {
+        self.about = Some(about);
+        self
+    }
+    /// Add a section to the end of the help. Sections without any entries aren't shown.
+    pub fn section(mut self, section: HelpSection) -> Self {
+        self.sections.push(section);
+        self
+    }
+    ///
+    /// Add the help of a subcommand.
+    ///
+    /// This isn't shown with the help of this command, which lists subcommands in a section,
+    /// but it's used to document every subcommand, such as in a `ManPage`.
+    pub fn subcommand(mut self, help: HelpInfo) -> Self {
+        self.subcommands.push(help);
+        self
+    }
+    /// Add an example of using the command, such as `grep -i hello src`. Examples are only shown by `--help`.
+    pub fn example(mut self, example: impl Into<Cow<'static, str>>) -> Self {
+        self.examples.push(example.into());
+        self
+    }
+    ///
+    /// Describe an environment variable that the command reads.
+    ///
+    /// These are only shown by `--help`, under `ENVIRONMENT`, and are also listed in a `ManPage`.
+    pub fn env(mut self, name: impl Into<Cow<'static, str>>, description: impl Into<Cow<'static, str>>) -> Self {
+        self.env.push((name.into(), description.into()));
+        self
+    }
+    /// Set text to show at the end of the help.
+    pub fn footer(mut self, footer: impl Into<Cow<'static, str>>) -> Self {
+        self.footer = Some(footer.into());
+        self
+    }
+    /// Set how the help is being shown.
+    #[doc(hidden)]
+    pub fn shown(mut self, requested: bool, kind: HelpKind) -> Self {
+        self.requested = requested;
+        self.kind = kind;
+        self
+    }
+
+    /// The name of the command.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// The usage line of the command, without the `Usage: ` at the start.
+    pub fn usage(&self) -> &str {
+        &self.usage
+    }
+    /// The description of the command.
+    pub fn about_text(&self) -> Option<&HelpText> {
+        self.about.as_ref()
+    }
+    /// The sections listing the command's arguments or subcommands.
+    pub fn sections(&self) -> &[HelpSection] {
+        &self.sections
+    }
+    /// The sections listing the command's arguments or subcommands, for adding or changing entries.
+    pub fn sections_mut(&mut self) -> &mut Vec<HelpSection> {
+        &mut self.sections
+    }
+    /// The help of each subcommand.
+    pub fn subcommands(&self) -> &[HelpInfo] {
+        &self.subcommands
+    }
+    /// The examples of using the command.
+    pub fn examples(&self) -> &[Cow<'static, str>] {
+        &self.examples
+    }
+    /// The environment variables that the command reads, with their descriptions.
+    pub fn env_vars(&self) -> &[(Cow<'static, str>, Cow<'static, str>)] {
+        &self.env
+    }
+    /// The text shown at the end of the help.
+    pub fn footer_text(&self) -> Option<&str> {
+        self.footer.as_deref()
+    }
+    ///
+    /// Whether the user asked for help with `--help` or `-h`.
+    ///
+    /// If this is `false`, help is being shown because required arguments are missing.
+    pub fn is_requested(&self) -> bool {
+        self.requested
+    }
+    ///
+    /// Whether this is the short help from `-h`, or the long help from `--help`.
+    ///
+    /// Help shown because required arguments are missing is short.
+    pub fn kind(&self) -> HelpKind {
+        self.kind
+    }
+    /// Lay out the help to fit within `width` columns.
+    pub fn render(&self, width: usize) -> String {
+        HelpRenderer::new().width(width).render(self)
+    }
+    ///
+    /// Lay out the help to fit within `width` columns, with bold headings and colored argument names.
+    ///
+    /// This is the same as `HelpInfo::render` unless the `color` feature is enabled.
+    pub fn render_styled(&self, width: usize) -> String {
+        HelpRenderer::new().width(width).styled(true).render(self)
+    }
+}
+
+/// Help is laid out to fit the terminal.
+impl Display for HelpInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&HelpRenderer::new().render(self))
+    }
+}
+
+/// A group of entries in a help page, such as `ARGUMENTS` or `FLAGS`.
+#[derive(Clone, Debug)]
+pub struct HelpSection {
+    pub heading: Cow<'static, str>,
+    pub entries: Vec<HelpEntry>,
+}
+
+impl HelpSection {
+    pub fn new(heading: impl Into<Cow<'static, str>>, entries: Vec<HelpEntry>) -> Self {
+        Self {
+            heading: heading.into(),
+            entries,
+        }
+    }
+}
+
+/// An argument or subcommand in a help page.
+#[derive(Clone, Debug)]
+pub struct HelpEntry {
+    /// The name shown in help, such as `-v, --verbose`.
+    pub name: Cow<'static, str>,
+    pub help: HelpText,
+}
+
+impl HelpEntry {
+    pub fn new(name: impl Into<Cow<'static, str>>, help: HelpText) -> Self {
+        Self {
+            name: name.into(),
+            help,
+        }
+    }
+}
+
+/// The short and long descriptions of a command or argument.
+#[derive(Clone, Debug, Default)]
+pub struct HelpText {
+    /// A summary, shown by `-h`.
+    pub short: Cow<'static, str>,
+    /// The full description, shown by `--help`.
+    pub long: Cow<'static, str>,
+    /// Notes such as `[optional]`, shown after the summary by `-h` and on their own line by `--help`.
+    pub tags: Vec<Cow<'static, str>>,
+    /// Notes that are only shown by `--help`, after the tags, such as `[default: 8]`.
+    pub details: Vec<Cow<'static, str>>,
+}
+
+impl HelpText {
+    pub fn new(short: impl Into<Cow<'static, str>>, long: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            short: short.into(),
+            long: long.into(),
+            tags: Vec::new(),
+            details: Vec::new(),
+        }
+    }
+    /// Add notes that are shown by both `-h` and `--help`.
+    pub fn tags<T: Into<Cow<'static, str>>>(mut self, tags: impl IntoIterator<Item = T>) -> Self {
+        self.tags.extend(tags.into_iter().map(Into::into));
+        self
+    }
+    /// Add notes that are only shown by `--help`.
+    pub fn details<T: Into<Cow<'static, str>>>(mut self, details: impl IntoIterator<Item = T>) -> Self {
+        self.details.extend(details.into_iter().map(Into::into));
+        self
+    }
+    fn get(&self, kind: HelpKind) -> &str {
+        match kind {
+            HelpKind::Short => &self.short,
+            HelpKind::Long => &self.long,
+        }
+    }
+    /// The summary followed by the tags, on one line.
+    fn short_line(&self) -> String {
+        std::iter::once(&self.short)
+            .chain(&self.tags)
+            .filter(|s| !s.is_empty())
+            .map(|s| s.as_ref())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+    /// The tags and details that `--help` shows under the description, if there are any.
+    pub(crate) fn notes(&self) -> Option<String> {
+        let notes: Vec<&str> = self.tags.iter().chain(&self.details).map(|s| s.as_ref()).collect();
+        if notes.is_empty() {
+            None
+        } else {
+            Some(notes.join(" "))
+        }
+    }
+}
+
+///
+/// The version of a command, from `#[version]`.
+///
+/// This is returned when the user passes `--version` or `-V`, and is displayed as the name followed by the version.
+#[derive(Debug)]
+pub struct VersionInfo {
+    name: String,
+    version: &'static str,
+}
+
+impl VersionInfo {
+    #[doc(hidden)]
+    pub fn new(name: impl Into<String>, version: &'static str) -> Self {
+        Self {
+            name: name.into(),
+            version,
+        }
+    }
+    /// The name of the program.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// The version string, such as `1.2.3`.
+    pub fn version(&self) -> &'static str {
+        self.version
+    }
+}
+
+impl Display for VersionInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.name, self.version)
+    }
+}
+
+///
+/// Lays out `HelpInfo` as text.
+///
+/// ```ignore
+/// let text = HelpRenderer::new().width(60).kind(HelpKind::Short).render(&Grep::help());
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct HelpRenderer {
+    width: Option<usize>,
+    kind: Option<HelpKind>,
+    styled: bool,
+}
+
+impl HelpRenderer {
+    /// Lay out help to fit the terminal, without any styling.
+    pub fn new() -> Self {
+        Self {
+            width: None,
+            kind: None,
+            styled: false,
+        }
+    }
+    /// Fit the help within `width` columns, rather than the width of the terminal.
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = Some(width);
+        self
+    }
+    /// Show short or long help, rather than the kind that the user asked for.
+    pub fn kind(mut self, kind: HelpKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+    ///
+    /// Whether to use bold headings and colored argument names.
+    ///
+    /// This has no effect unless the `color` feature is enabled.
+    pub fn styled(mut self, styled: bool) -> Self {
+        self.styled = styled;
+        self
+    }
+
+    pub fn render(&self, help: &HelpInfo) -> String {
+        let width = self.width.unwrap_or_else(terminal_width);
+        let kind = self.kind.unwrap_or(help.kind);
+        let style = Style::new(self.styled);
+
+        let mut out = String::new();
+        style.heading("Help", &mut out);
+        out.push_str(" - ");
+        out.push_str(&help.name);
+        out.push('\n');
+        style.heading("Usage:", &mut out);
+        out.push(' ');
+        wrap(&help.usage, USAGE_INDENT, width, &mut out);
+        if let Some(about) = &help.about {
+            out.push_str("\n\n");
+            wrap(about.get(kind), 0, width, &mut out);
+        }
+        for section in help.sections.iter().filter(|s| !s.entries.is_empty()) {
+            out.push_str("\n\n");
+            style.heading(&format!("{}:", section.heading), &mut out);
+            match kind {
+                HelpKind::Short => render_short(section, width, style, &mut out),
+                HelpKind::Long => render_long(section, width, style, &mut out),
+            }
+        }
+        if kind == HelpKind::Long && !help.env.is_empty() {
+            out.push_str("\n\n");
+            style.heading("ENVIRONMENT:", &mut out);
+            for (i, (name, description)) in help.env.iter().enumerate() {
+                if i > 0 {
+                    out.push('\n');
+                }
+                out.push('\n');
+                push_spaces(INDENT, &mut out);
+                style.name(name, &mut out);
+                out.push('\n');
+                push_spaces(LONG_INDENT, &mut out);
+                wrap(description, LONG_INDENT, width, &mut out);
+            }
+        }
+        if kind == HelpKind::Long && !help.examples.is_empty() {
+            out.push_str("\n\n");
+            style.heading("EXAMPLES:", &mut out);
+            for example in &help.examples {
+                out.push('\n');
+                push_spaces(INDENT, &mut out);
+                wrap(example, INDENT, width, &mut out);
+            }
+        }
+        if let Some(footer) = &help.footer {
+            out.push_str("\n\n");
+            wrap(footer, 0, width, &mut out);
+        }
+        out
+    }
+}
+
+impl Default for HelpRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The width used if it can't be read from `COLUMNS`.
+const DEFAULT_WIDTH: usize = 80;
+/// The indentation before each entry in a section.
+const INDENT: usize = 4;
+/// The indentation of descriptions in long help, which go on the line after their entry's name.
+const LONG_INDENT: usize = 8;
+/// The space between an entry's name and its description.
+const GAP: usize = 2;
+/// The indentation of a usage line that is too long to fit on one line.
+const USAGE_INDENT: usize = "Usage: ".len();
+/// The narrowest that a description is allowed to be wrapped.
+const MIN_DESCRIPTION: usize = 20;
+
+/// The width of the terminal, according to the `COLUMNS` environment variable.
+fn terminal_width() -> usize {
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|c| c.trim().parse().ok())
+        .filter(|&c| c > 0)
+        .unwrap_or(DEFAULT_WIDTH)
+}
+
+/// Show each entry on one line, with the short descriptions lined up in a column.
+fn render_short(section: &HelpSection, width: usize, style: Style, out: &mut String) {
+    // Line up every description in this section,
+    // unless a name is so long that there would be no room left for descriptions.
+    let max_column = width.saturating_sub(MIN_DESCRIPTION).max(INDENT + GAP);
+    let column = section
+        .entries
+        .iter()
+        .map(|e| INDENT + len(&e.name) + GAP)
+        .filter(|&c| c <= max_column)
+        .max()
+        .unwrap_or(INDENT + GAP);
+
+    for entry in &section.entries {
+        out.push('\n');
+        push_spaces(INDENT, out);
+        style.name(&entry.name, out);
+        let short = entry.help.short_line();
+        if short.is_empty() {
+            continue;
+        }
+        let used = INDENT + len(&entry.name);
+        // Names that are too long get their description on the next line.
+        if used + GAP > column {
+            out.push('\n');
+            push_spaces(column, out);
+        } else {
+            push_spaces(column - used, out);
+        }
+        wrap(&short, column, width, out);
+    }
+}
+/// Show each entry's long description under its name, with a blank line between entries.
+fn render_long(section: &HelpSection, width: usize, style: Style, out: &mut String) {
+    for (i, entry) in section.entries.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        out.push('\n');
+        push_spaces(INDENT, out);
+        style.name(&entry.name, out);
+        if !entry.help.long.is_empty() {
+            out.push('\n');
+            push_spaces(LONG_INDENT, out);
+            wrap(&entry.help.long, LONG_INDENT, width, out);
+        }
+        // Notes go on their own line, so they aren't mistaken for part of the last paragraph.
+        if let Some(notes) = entry.help.notes() {
+            out.push('\n');
+            push_spaces(LONG_INDENT, out);
+            wrap(&notes, LONG_INDENT, width, out);
+        }
+    }
+}
+
+///
+/// Join the name of the program with the rest of a usage line.
+#[doc(hidden)]
+pub fn usage_line(program: &str, usage: &str) -> String {
+    if usage.is_empty() {
+        program.to_string()
+    } else {
+        format!("{} {}", program, usage)
+    }
+}
+
+/// The number of columns taken up by a string.
+fn len(s: &str) -> usize {
+    s.chars().count()
+}
+
+fn push_spaces(n: usize, out: &mut String) {
+    out.extend(std::iter::repeat(' ').take(n));
+}
+
+///
+/// Wrap `text` to fit in the columns between `indent` and `width`, assuming that the cursor is already at `indent`.
+/// Lines after the first are indented to line up with the first.
+fn wrap(text: &str, indent: usize, width: usize, out: &mut String) {
+    let avail = width.saturating_sub(indent).max(MIN_DESCRIPTION);
+    for (i, line) in text.lines().enumerate() {
+        if i > 0 {
+            out.push('\n');
+            if !line.trim().is_empty() {
+                push_spaces(indent, out);
+            }
+        }
+        let mut col = 0;
+        for word in line.split_whitespace() {
+            if col > 0 && col + 1 + len(word) > avail {
+                out.push('\n');
+                push_spaces(indent, out);
+                col = 0;
+            } else if col > 0 {
+                out.push(' ');
+                col += 1;
+            }
+            out.push_str(word);
+            col += len(word);
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use crate::{ArgKind, ArgSpec, CommandSpec};
+
+impl CommandSpec {
+    ///
+    /// The version of the schema written by `to_json`.
+    ///
+    /// This only changes when fields are removed or their meaning changes; new fields can be added without changing it.
+    pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+    ///
+    /// Write this command, its arguments and its subcommands as JSON.
+    ///
+    /// The document is an object with the `schema_version` and the `command`:
+    /// ```text
+    /// {
+    ///   "schema_version": 1,
+    ///   "command": {
+    ///     "name": "pkg", "about": "...", "long_about": "...", "usage": "<COMMAND>", "version": true,
+    ///     "args": [
+    ///       {
+    ///         "kind": "named", "name": "profile", "long": "--profile", "short": "-p",
+    ///         "help": "...", "long_help": "...", "type": "Option<Profile>",
+    ///         "required": false, "variadic": false, "default": null,
+    ///         "values": ["debug", "release"], "dynamic_completion": false
+    ///       }
+    ///     ],
+    ///     "subcommands": [ ... ]
+    ///   }
+    /// }
+    /// ```
+    /// `long`, `short`, `default` and `values` are `null` if the argument doesn't have them.
+    /// `kind` is one of `positional`, `named` or `flag`.
+    pub fn to_json(&self) -> String {
+        let doc = Json::Object(vec![
+            ("schema_version", Json::Number(Self::JSON_SCHEMA_VERSION)),
+            ("command", command(self)),
+        ]);
+        let mut out = String::new();
+        doc.write(0, &mut out);
+        out.push('\n');
+        out
+    }
+}
+
+fn command(cmd: &CommandSpec) -> Json {
+    Json::Object(vec![
+        ("name", Json::str(cmd.name)),
+        ("about", Json::str(cmd.about)),
+        ("long_about", Json::str(cmd.long_about)),
+        ("usage", Json::str(cmd.usage)),
+        ("version", Json::Bool(cmd.version)),
+        ("args", Json::Array(cmd.args.iter().map(arg).collect())),
+        ("subcommands", Json::Array(cmd.subcommands.iter().map(command).collect())),
+    ])
+}
+
+fn arg(arg: &ArgSpec) -> Json {
+    let kind = match arg.kind {
+        ArgKind::Positional => "positional",
+        ArgKind::Named => "named",
+        ArgKind::Flag => "flag",
+    };
+    let values = arg
+        .values
+        .map_or(Json::Null, |values| Json::Array(values.iter().copied().map(Json::str).collect()));
+    Json::Object(vec![
+        ("kind", Json::str(kind)),
+        ("name", Json::str(arg.name)),
+        ("long", arg.long.map_or(Json::Null, Json::str)),
+        ("short", arg.short.map_or(Json::Null, Json::str)),
+        ("help", Json::str(arg.help)),
+        ("long_help", Json::str(arg.long_help)),
+        ("type", Json::str(arg.ty)),
+        ("required", Json::Bool(arg.required)),
+        ("variadic", Json::Bool(arg.variadic)),
+        ("default", arg.default.as_deref().map_or(Json::Null, Json::str)),
+        ("values", values),
+        ("dynamic_completion", Json::Bool(arg.complete.is_some())),
+    ])
+}
+
+/// A JSON value, which is written with two spaces of indentation.
+enum Json {
+    Null,
+    Bool(bool),
+    Number(u32),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(&'static str, Json)>),
+}
+
+impl Json {
+    fn str(s: &str) -> Self {
+        Json::String(s.to_string())
+    }
+    fn write(&self, indent: usize, out: &mut String) {
+        match self {
+            Json::Null => out.push_str("null"),
+            Json::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+            Json::Number(n) => out.push_str(&n.to_string()),
+            Json::String(s) => quote(s, out),
+            Json::Array(items) if items.is_empty() => out.push_str("[]"),
+            Json::Array(items) => {
+                out.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    out.push_str(if i == 0 { "\n" } else { ",\n" });
+                    out.push_str(&"  ".repeat(indent + 1));
+                    item.write(indent + 1, out);
+                }
+                out.push('\n');
+                out.push_str(&"  ".repeat(indent));
+                out.push(']');
+            }
+            Json::Object(fields) => {
+                out.push('{');
+                for (i, (key, val)) in fields.iter().enumerate() {
+                    out.push_str(if i == 0 { "\n" } else { ",\n" });
+                    out.push_str(&"  ".repeat(indent + 1));
+                    quote(key, out);
+                    out.push_str(": ");
+                    val.write(indent + 1, out);
+                }
+                out.push('\n');
+                out.push_str(&"  ".repeat(indent));
+                out.push('}');
+            }
+        }
+    }
+}
+
+/// Write a JSON string, escaping quotes, backslashes and control characters.
+fn quote(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
@@ -1,96 +1,181 @@
-use std::error::Error as StdError;
+use std::ffi::OsString;
 
 pub use type_cli_derive::CLI;
 
 mod args;
-pub use args::{Argument, Flag, OptionalArg};
+mod builder;
+pub mod completions;
+#[doc(hidden)]
+pub mod engine;
+mod errors;
+mod help;
+#[cfg(feature = "json")]
+mod json;
+mod man;
+mod markdown;
+mod process;
+mod spec;
+mod style;
+mod suggest;
+pub use args::{Argument, ArgumentOs, BorrowedArgument, Flag, OptionalArg, VariadicArg};
+pub use builder::{Arg, Command, Matches};
+#[doc(hidden)]
+pub use args::{ArgParser, RawArg, ViaArgument, ViaArgumentOs, ViaBorrowed, ViaFromStr};
+pub use errors::{ArgRef, Error, ErrorKind, Errors};
+#[doc(hidden)]
+pub use errors::ErrorCollector;
+pub use help::{HelpEntry, HelpInfo, HelpKind, HelpRenderer, HelpSection, HelpText, VersionInfo};
+#[doc(hidden)]
+pub use help::usage_line;
+pub use man::ManPage;
+pub use markdown::MarkdownReference;
+pub use process::{Exit, Outcome, ProcessConfig};
+#[doc(hidden)]
+pub use process::try_process_from;
+pub use spec::{ArgKind, ArgSpec, CommandSpec};
+#[doc(hidden)]
+pub use spec::{DefaultText, ViaDebug, ViaNoDebug};
+pub use style::{ColorChoice, UnknownColorChoice};
 
-pub trait CLI: Sized {
+pub trait CLI: ParseArgs<OsString> {
     ///
     /// Parses the arguments as a command-line interface of the current type,
     /// returning errors as a value for manul handling.
     ///
     /// If you don't need fine control over program flow, use `CLI::processs` instead.
-    fn parse(args: impl std::iter::Iterator<Item = String>) -> Result<Parse<Self>, Error>;
+    fn parse(args: impl std::iter::Iterator<Item = String>) -> Result<Parse<Self>, Error> {
+        Self::parse_os(args)
+    }
+    ///
+    /// Parses the arguments as a command-line interface of the current type,
+    /// allowing arguments that aren't valid unicode.
+    ///
+    /// Only arguments whose type needs a `str` will fail on invalid unicode;
+    /// `OsString` and `PathBuf` arguments receive the raw value.
+    fn parse_os(args: impl IntoIterator<Item = impl Into<OsString>>) -> Result<Parse<Self>, Error> {
+        Self::parse_args(args.into_iter().map(Into::into), ErrorCollector::first()).map_err(Errors::into_first)
+    }
+    ///
+    /// Parses the arguments as a command-line interface of the current type,
+    /// continuing after an error so that every mistake can be reported at once.
+    ///
+    /// Help info is still returned as soon as `--help` or `-h` is found.
+    fn parse_all_errors(args: impl IntoIterator<Item = impl Into<OsString>>) -> Result<Parse<Self>, Errors> {
+        Self::parse_args(args.into_iter().map(Into::into), ErrorCollector::all())
+    }
+    ///
+    /// Help info about this command, without parsing any arguments.
+    ///
+    /// This is the long help, as if the user had passed `--help`.
+    fn help() -> HelpInfo;
+    /// Implemented by `#[derive(CLI)]`.
+    #[doc(hidden)]
+    fn help_for(program: &str) -> HelpInfo;
+    ///
+    /// The long help of this command, with the help of each of its subcommands added, for `ManPage`.
+    ///
+    /// Implemented by `#[derive(CLI)]`.
+    #[doc(hidden)]
+    fn help_tree() -> HelpInfo;
     ///
-    /// Parses `std::env::args()` as a command-line interface of the current type.
+    /// A description of this command, its arguments and its subcommands, without parsing any arguments.
+    fn spec() -> CommandSpec;
     ///
-    /// If an error occurs while parsing, it will be send to stderr and the process will exit.
-    /// If the user enters `--help` or `-h`, help info will be sent to stderr and the process will exit.
+    /// Parses `std::env::args_os()` as a command-line interface of the current type.
     ///
+    /// If an error occurs while parsing, it will be sent to stderr and the process will exit with code `2`.
+    /// If the user enters `--help` or `-h`, help info will be sent to stdout and the process will exit with code `0`.
+    /// If required arguments are missing, help info will be sent to stderr and the process will exit with code `2`.
+    /// If it was run by a completion script, with `completions::COMPLETE_VAR` set,
+    /// completions will be sent to stdout and the process will exit with code `0`.
+    ///
+    /// To change this behaviour, use `CLI::process_with`.
+    /// This calls `std::process::exit`, which doesn't run destructors;
+    /// to avoid that, use `CLI::run` or `CLI::try_process`.
     /// If you want finer control over program flow, use `CLI::parse` instead.
     fn process() -> Self {
-        match Self::parse(std::env::args()) {
-            Ok(Parse::Success(val)) => val,
-            Ok(Parse::Help(help)) => {
-                eprintln!("{}", help);
-                std::process::exit(1);
-            }
-            Err(e) => {
-                eprintln!("{}", e);
-                std::process::exit(101);
-            }
+        Self::process_with(ProcessConfig::default())
+    }
+    ///
+    /// Parses `std::env::args_os()` as a command-line interface of the current type,
+    /// reporting help and errors as described by `config`.
+    fn process_with(config: ProcessConfig) -> Self {
+        Self::try_process_with(config).unwrap_or_else(|exit| exit.exit())
+    }
+    ///
+    /// Parses `std::env::args_os()` as a command-line interface of the current type.
+    ///
+    /// If help info or an error should be shown, it is returned as an `Exit`,
+    /// which prints it with the right exit code when returned from `main`.
+    fn try_process() -> Result<Self, Exit> {
+        Self::try_process_with(ProcessConfig::default())
+    }
+    ///
+    /// Parses `std::env::args_os()` as a command-line interface of the current type,
+    /// reporting help and errors as described by `config`.
+    fn try_process_with(config: ProcessConfig) -> Result<Self, Exit> {
+        let args: Vec<OsString> = std::env::args_os().collect();
+        process::try_process_from(config, std::env::var_os(completions::COMPLETE_VAR), args)
+    }
+    ///
+    /// Parses `std::env::args_os()` as a command-line interface of the current type, and runs `f` with the result.
+    ///
+    /// The returned `Outcome` can be returned from `main`: it shows help info or errors and exits with the right code,
+    /// without skipping any destructors.
+    fn run<T: std::process::Termination>(f: impl FnOnce(Self) -> T) -> Outcome<T> {
+        match Self::try_process() {
+            Ok(val) => Outcome::Done(f(val)),
+            Err(exit) => Outcome::Exit(exit),
         }
     }
 }
 
 ///
-/// A result of successful command-line interface parsing.
+/// Parses commands from arguments that are borrowed from the caller, such as the words of a line typed into a REPL.
 ///
-/// This is either a data structure holding the arguments passed to the program,
-/// or a string containing help info about the current command.
-pub enum Parse<T: CLI> {
-    Success(T),
-    Help(HelpInfo),
-}
-
-pub struct HelpInfo(pub &'static str);
-
-impl std::fmt::Display for HelpInfo {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
+/// This is implemented for every type that derives `CLI`.
+/// Types with a lifetime, such as `struct Get<'a> { key: &'a str }`, can keep `&str`, `&OsStr` and `&Path`
+/// fields that point into the arguments; they can only be parsed this way, so they don't implement `CLI`.
+/// ```ignore
+/// let words: Vec<&str> = line.split_whitespace().collect();
+/// match Get::parse_borrowed(&words)? { ... }
+/// ```
+pub trait ParseBorrowed<'a>: ParseArgs<&'a str> {
+    ///
+    /// Parses the arguments as a command-line interface of the current type, without copying them.
+    ///
+    /// As with `CLI::parse`, the first argument is the name of the program.
+    fn parse_borrowed(args: &'a [&'a str]) -> Result<Parse<Self>, Error> {
+        Self::parse_args(args.iter().copied(), ErrorCollector::first()).map_err(Errors::into_first)
+    }
+    ///
+    /// Parses the arguments as a command-line interface of the current type, without copying them,
+    /// continuing after an error so that every mistake can be reported at once.
+    fn parse_borrowed_all_errors(args: &'a [&'a str]) -> Result<Parse<Self>, Errors> {
+        Self::parse_args(args.iter().copied(), ErrorCollector::all())
     }
 }
+impl<'a, T: ParseArgs<&'a str>> ParseBorrowed<'a> for T {}
 
-#[derive(thiserror::Error)]
-pub enum Error {
-    #[error("Expected an argument named `{0}`")]
-    ExpectedNamed(&'static str),
-    #[error("Expected an argument at position `{0}`")]
-    ExpectedPositional(usize),
-    #[error("Expected a value after argument `{0}`")]
-    ExpectedValue(&'static str),
-    #[error("Unknown flag `{0}`")]
-    UnknownFlag(String),
-    #[error("Unexpected positional argument `{0}`")]
-    ExtraArg(String),
-    #[error("Unknown subcommand `{0}`")]
-    UnknownSub(String),
-    #[error("Error parsing {0}:\n{1}")]
-    Parse(ArgRef, Box<dyn StdError>),
-}
-
-/// A way to refer to an argument in an error.
-pub enum ArgRef {
-    Positional(usize),
-    Named(&'static str),
-}
-use std::fmt::{self, Display};
-impl Display for ArgRef {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            &ArgRef::Positional(index) => {
-                write!(f, "positional argument `{}`", index)
-            }
-            &ArgRef::Named(name) => {
-                write!(f, "argument `{}`", name)
-            }
-        }
-    }
+///
+/// Parses a command from arguments of type `A`, which is either `OsString` or `&str`.
+///
+/// Implemented by `#[derive(CLI)]`.
+#[doc(hidden)]
+pub trait ParseArgs<A>: Sized {
+    fn parse_args(args: impl Iterator<Item = A>, errors: ErrorCollector) -> Result<Parse<Self>, Errors>;
 }
 
-impl std::fmt::Debug for Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        std::fmt::Display::fmt(self, f)
-    }
+///
+/// A result of successful command-line interface parsing.
+///
+/// This is either a data structure holding the arguments passed to the program,
+/// help info about the current command, or the version of the program.
+// `Parse` is returned once and matched right away, so it isn't worth boxing the help.
+#[allow(clippy::large_enum_variant)]
+pub enum Parse<T> {
+    Success(T),
+    Help(HelpInfo),
+    /// The user passed `--version` or `-V` to a command with a `#[version]` attribute.
+    Version(VersionInfo),
 }
@@ -0,0 +1,249 @@
+use crate::{HelpInfo, HelpSection, CLI};
+use std::borrow::Cow;
+use std::fmt::{self, Display};
+
+///
+/// A manual page for a command, written in roff for `man`.
+///
+/// The page is made from the command's help: the usage line is the `SYNOPSIS`, the long description is the
+/// `DESCRIPTION`, and arguments and flags are listed under `OPTIONS`.
+/// Each subcommand gets a subsection under `SUBCOMMANDS`, with its own usage line, description and options.
+/// Environment variables described by the help of the command and its subcommands are listed under `ENVIRONMENT`,
+/// and examples of the command and its subcommands are listed under `EXAMPLES`.
+///
+/// ```ignore
+/// std::fs::write("pkg.1", ManPage::new::<Pkg>().render())?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct ManPage {
+    help: HelpInfo,
+    section: Cow<'static, str>,
+    env: Vec<(Cow<'static, str>, Cow<'static, str>)>,
+}
+
+impl ManPage {
+    /// The manual page for the command `T`, in section `1`.
+    pub fn new<T: CLI>() -> Self {
+        Self::from_help(T::help_tree())
+    }
+    /// A manual page made from `help`, in section `1`.
+    pub fn from_help(help: HelpInfo) -> Self {
+        Self {
+            help,
+            section: "1".into(),
+            env: Vec::new(),
+        }
+    }
+    /// The section of the manual that the page belongs in, such as `8` for system administration commands.
+    pub fn section(mut self, section: impl Into<Cow<'static, str>>) -> Self {
+        self.section = section.into();
+        self
+    }
+    ///
+    /// Describe an environment variable that the program reads.
+    ///
+    /// These are listed under `ENVIRONMENT`, before the variables that `type-cli` itself reads.
+    pub fn env(mut self, name: impl Into<Cow<'static, str>>, description: impl Into<Cow<'static, str>>) -> Self {
+        self.env.push((name.into(), description.into()));
+        self
+    }
+
+    /// Write the page in roff.
+    pub fn render(&self) -> String {
+        let help = &self.help;
+        let mut out = format!(".TH {} {}\n", escape(&help.name().to_uppercase()), escape(&self.section));
+
+        out.push_str(".SH NAME\n");
+        out.push_str(&escape(help.name()));
+        if let Some(about) = help.about_text().filter(|a| !a.short.is_empty()) {
+            out.push_str(" \\- ");
+            out.push_str(&escape(&about.short));
+        }
+        out.push('\n');
+
+        out.push_str(".SH SYNOPSIS\n");
+        out.push_str(&synopsis(help));
+
+        if let Some(about) = help.about_text().filter(|a| !a.long.is_empty()) {
+            out.push_str(".SH DESCRIPTION\n");
+            out.push_str(&paragraphs(&about.long, ".PP"));
+        }
+
+        let (subcommands, options): (Vec<_>, Vec<_>) = help
+            .sections()
+            .iter()
+            .partition(|s| s.heading == "SUBCOMMANDS");
+        if options.iter().any(|s| !s.entries.is_empty()) {
+            out.push_str(".SH OPTIONS\n");
+            out.push_str(&entries(&options));
+        }
+        if !help.subcommands().is_empty() {
+            out.push_str(".SH SUBCOMMANDS\n");
+            for sub in help.subcommands() {
+                subcommand(sub, &mut out);
+            }
+        } else if subcommands.iter().any(|s| !s.entries.is_empty()) {
+            // The help of each subcommand isn't known, so just list them.
+            out.push_str(".SH SUBCOMMANDS\n");
+            out.push_str(&entries(&subcommands));
+        }
+
+        out.push_str(".SH ENVIRONMENT\n");
+        let library_env = [
+            Some(("COLUMNS", "The width of the terminal, which help is laid out to fit.")),
+            color_env("NO_COLOR", "If this is set, help and errors aren't shown in color."),
+            color_env(
+                "CLICOLOR_FORCE",
+                "If this is set to anything but 0, help and errors are shown in color, even if they're not printed to a terminal.",
+            ),
+        ];
+        let library_env = library_env.iter().flatten().copied();
+        let mut env = Vec::new();
+        collect_env(help, &mut env);
+        let env = env.into_iter().chain(self.env.iter().map(|(name, description)| (&**name, &**description)));
+        let mut seen = Vec::new();
+        for (name, description) in env.chain(library_env) {
+            // Subcommands can read the same variables, which are only described once.
+            if seen.contains(&name) {
+                continue;
+            }
+            seen.push(name);
+            out.push_str(&format!(".TP\n\\fB{}\\fR\n{}", escape(name), paragraphs(description, ".IP")));
+        }
+
+        let mut examples = Vec::new();
+        collect_examples(help, &mut examples);
+        if !examples.is_empty() {
+            out.push_str(".SH EXAMPLES\n");
+            for example in examples {
+                out.push_str(&format!(".PP\n{}\n", escape(example)));
+            }
+        }
+        out
+    }
+}
+
+impl Display for ManPage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render())
+    }
+}
+
+/// An environment variable that's only read if the `color` feature is enabled.
+fn color_env(name: &'static str, description: &'static str) -> Option<(&'static str, &'static str)> {
+    if cfg!(feature = "color") {
+        Some((name, description))
+    } else {
+        None
+    }
+}
+
+/// A subsection describing a subcommand, and any subcommands of its own.
+fn subcommand(help: &HelpInfo, out: &mut String) {
+    out.push_str(&format!(".SS {}\n", escape(split_usage(help).0)));
+    out.push_str(&synopsis(help));
+    if let Some(about) = help.about_text().filter(|a| !a.long.is_empty()) {
+        out.push_str(".PP\n");
+        out.push_str(&paragraphs(&about.long, ".PP"));
+    }
+    let sections: Vec<_> = help.sections().iter().collect();
+    out.push_str(&entries(&sections));
+    for sub in help.subcommands() {
+        subcommand(sub, out);
+    }
+}
+
+/// The examples of a command, followed by the examples of each of its subcommands.
+fn collect_env<'a>(help: &'a HelpInfo, out: &mut Vec<(&'a str, &'a str)>) {
+    out.extend(help.env_vars().iter().map(|(name, description)| (&**name, &**description)));
+    for sub in help.subcommands() {
+        collect_env(sub, out);
+    }
+}
+
+fn collect_examples<'a>(help: &'a HelpInfo, out: &mut Vec<&'a str>) {
+    out.extend(help.examples().iter().map(|e| &**e));
+    for sub in help.subcommands() {
+        collect_examples(sub, out);
+    }
+}
+
+/// The usage line of a command, with the name of the command in bold.
+fn synopsis(help: &HelpInfo) -> String {
+    let (name, args) = split_usage(help);
+    format!("\\fB{}\\fR{}\n", escape(name), escape(args))
+}
+
+///
+/// Split the usage line of a command into the name of the command and its arguments.
+///
+/// The name is every word before the first argument, such as `cargo build` in `cargo build [OPTIONS]`.
+fn split_usage(help: &HelpInfo) -> (&str, &str) {
+    let usage = help.usage();
+    let args_at = usage
+        .match_indices(' ')
+        .map(|(i, _)| i)
+        .find(|&i| !usage[i + 1..].starts_with(|c: char| c.is_alphanumeric()))
+        .unwrap_or(usage.len());
+    usage.split_at(args_at)
+}
+
+/// A tagged paragraph for each entry in `sections`, with its name and long description.
+fn entries(sections: &[&HelpSection]) -> String {
+    let mut out = String::new();
+    for entry in sections.iter().flat_map(|s| &s.entries) {
+        out.push_str(".TP\n");
+        out.push_str(&entry_name(&entry.name));
+        out.push('\n');
+        if !entry.help.long.is_empty() {
+            out.push_str(&paragraphs(&entry.help.long, ".IP"));
+        }
+        if let Some(notes) = entry.help.notes() {
+            if !entry.help.long.is_empty() {
+                out.push_str(".IP\n");
+            }
+            out.push_str(&escape(&notes));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// The name of an entry, such as `-v, --verbose`, with flags in bold and other names in italics.
+fn entry_name(name: &str) -> String {
+    name.split(", ")
+        .map(|name| {
+            let font = if name.starts_with('-') { 'B' } else { 'I' };
+            format!("\\f{}{}\\fR", font, escape(name))
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Text with a blank line between each paragraph, which are separated by the `macro` in roff.
+fn paragraphs(text: &str, macro_: &str) -> String {
+    let mut out = text
+        .split("\n\n")
+        .map(escape)
+        .collect::<Vec<_>>()
+        .join(&format!("\n{}\n", macro_));
+    out.push('\n');
+    out
+}
+
+/// Escape text so that roff shows it as written.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\e")
+        .replace('-', "\\-")
+        .split('\n')
+        .map(|line| {
+            // Lines starting with these are read as requests.
+            if line.starts_with('.') || line.starts_with('\'') {
+                format!("\\&{}", line)
+            } else {
+                line.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,128 @@
+use crate::{usage_line, ArgKind, ArgSpec, CommandSpec, CLI};
+use std::fmt::{self, Display};
+
+///
+/// A reference for a command in Markdown, such as for a project's documentation.
+///
+/// Each command gets a heading with its description and usage line, followed by a table of its subcommands
+/// and a table of its arguments, with their type, whether they're required, their default and their description.
+/// Subcommands get their own sections, with headings one level deeper.
+///
+/// ```ignore
+/// std::fs::write("docs/cli.md", MarkdownReference::new::<Pkg>().render())?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct MarkdownReference {
+    spec: CommandSpec,
+    heading_level: usize,
+}
+
+impl MarkdownReference {
+    /// The reference for the command `T`, starting with a level 1 heading.
+    pub fn new<T: CLI>() -> Self {
+        Self::from_spec(T::spec())
+    }
+    /// The reference for the command described by `spec`, starting with a level 1 heading.
+    pub fn from_spec(spec: CommandSpec) -> Self {
+        Self {
+            spec,
+            heading_level: 1,
+        }
+    }
+    /// The level of the command's heading, such as `2` for `##`, so the reference can go inside another document.
+    pub fn heading_level(mut self, level: usize) -> Self {
+        self.heading_level = level.clamp(1, 6);
+        self
+    }
+
+    /// Write the reference in Markdown.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        command(&self.spec, self.spec.name.to_string(), self.heading_level, &mut out);
+        out.truncate(out.trim_end().len());
+        out.push('\n');
+        out
+    }
+}
+
+impl Display for MarkdownReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render())
+    }
+}
+
+/// The section for a command, and the sections of each of its subcommands.
+fn command(cmd: &CommandSpec, path: String, level: usize, out: &mut String) {
+    out.push_str(&format!("{} {}\n\n", "#".repeat(level), path));
+    if !cmd.long_about.is_empty() {
+        out.push_str(cmd.long_about);
+        out.push_str("\n\n");
+    }
+    out.push_str(&format!("**Usage:** `{}`\n\n", usage_line(&path, cmd.usage)));
+
+    if !cmd.subcommands.is_empty() {
+        out.push_str("| Subcommand | Description |\n| --- | --- |\n");
+        for sub in &cmd.subcommands {
+            let anchor = format!("{} {}", path, sub.name).replace(' ', "-").to_lowercase();
+            out.push_str(&format!("| [`{}`](#{}) | {} |\n", sub.name, anchor, cell(sub.about)));
+        }
+        out.push('\n');
+    }
+
+    out.push_str("| Argument | Type | Required | Default | Description |\n| --- | --- | --- | --- | --- |\n");
+    for arg in cmd.positionals().chain(&cmd.options()) {
+        let required = match (arg.required, arg.variadic) {
+            (_, true) => "no (variadic)",
+            (true, false) => "yes",
+            (false, false) => "no",
+        };
+        let default = arg.default.as_ref().map(|d| format!("`{}`", d)).unwrap_or_default();
+        out.push_str(&format!(
+            "| `{}` | `{}` | {} | {} | {} |\n",
+            arg_name(arg),
+            arg.ty,
+            required,
+            cell(&default),
+            cell(&description(arg)),
+        ));
+    }
+    out.push('\n');
+
+    for sub in &cmd.subcommands {
+        command(sub, format!("{} {}", path, sub.name), (level + 1).min(6), out);
+    }
+}
+
+/// How an argument is written, such as `<FILE>`, `-p, --profile <PROFILE>` or `-f, --force`.
+fn arg_name(arg: &ArgSpec) -> String {
+    let value = arg.name.to_uppercase();
+    match arg.kind {
+        ArgKind::Positional if arg.variadic => format!("[{}]...", value),
+        ArgKind::Positional if arg.required => format!("<{}>", value),
+        ArgKind::Positional => format!("[{}]", value),
+        ArgKind::Named => format!("{} <{}>", arg.names().collect::<Vec<_>>().join(", "), value),
+        ArgKind::Flag => arg.names().collect::<Vec<_>>().join(", "),
+    }
+}
+
+/// The long description of an argument, followed by the values it can take.
+fn description(arg: &ArgSpec) -> String {
+    let mut description = arg.long_help.to_string();
+    if let Some(values) = arg.values {
+        let values: Vec<_> = values.iter().map(|v| format!("`{}`", v)).collect();
+        if !description.is_empty() {
+            description.push_str("\n\n");
+        }
+        description.push_str(&format!("Possible values: {}.", values.join(", ")));
+    }
+    description
+}
+
+/// Text that fits in a table cell, which can't contain line breaks or unescaped pipes.
+fn cell(text: &str) -> String {
+    text.split("\n\n")
+        .map(|paragraph| paragraph.split('\n').collect::<Vec<_>>().join(" "))
+        .collect::<Vec<_>>()
+        .join("<br><br>")
+        .replace('|', "\\|")
+}
@@ -0,0 +1,343 @@
+use crate::style::Style;
+use crate::{completions, ColorChoice, Error, HelpInfo, HelpRenderer, Parse, VersionInfo, CLI};
+use std::ffi::OsString;
+use std::fmt::{self, Debug, Display};
+use std::process::{ExitCode, Termination};
+
+///
+/// Configures how `CLI::process_with` reports help and errors, and which exit codes it uses.
+///
+/// By default, help that the user asked for with `--help` or `-h` is sent to stdout with exit code `0`,
+/// as is the version from `--version` or `-V`.
+/// Help shown because arguments were missing, and parsing errors, are sent to stderr with exit code `2`,
+/// the same as `getopt`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProcessConfig {
+    help_exit_code: u8,
+    help_to_stdout: bool,
+    missing_args_exit_code: u8,
+    error_exit_code: u8,
+    color: ColorChoice,
+}
+
+impl ProcessConfig {
+    /// The exit code used by `getopt` and many shells for incorrect usage.
+    pub const USAGE: u8 = 2;
+    /// The exit code for incorrect usage from BSD's `sysexits.h`.
+    pub const EX_USAGE: u8 = 64;
+
+    /// The default config, which sends requested help to stdout with code `0` and uses code `2` for usage errors.
+    pub fn new() -> Self {
+        Self {
+            help_exit_code: 0,
+            help_to_stdout: true,
+            missing_args_exit_code: Self::USAGE,
+            error_exit_code: Self::USAGE,
+            color: ColorChoice::Auto,
+        }
+    }
+    /// Use `EX_USAGE` (64) for every usage error, as described in `sysexits.h`.
+    pub fn sysexits() -> Self {
+        Self::new()
+            .missing_args_exit_code(Self::EX_USAGE)
+            .error_exit_code(Self::EX_USAGE)
+    }
+
+    /// The exit code after showing help that the user asked for.
+    pub fn help_exit_code(mut self, code: u8) -> Self {
+        self.help_exit_code = code;
+        self
+    }
+    /// Whether help that the user asked for is sent to stdout, rather than stderr.
+    pub fn help_to_stdout(mut self, stdout: bool) -> Self {
+        self.help_to_stdout = stdout;
+        self
+    }
+    /// The exit code after showing help because arguments were missing.
+    pub fn missing_args_exit_code(mut self, code: u8) -> Self {
+        self.missing_args_exit_code = code;
+        self
+    }
+    /// The exit code after a parsing error.
+    pub fn error_exit_code(mut self, code: u8) -> Self {
+        self.error_exit_code = code;
+        self
+    }
+    ///
+    /// Whether to style help and errors with colors.
+    ///
+    /// This has no effect unless the `color` feature is enabled.
+    /// The value of the command's `--color` argument, if it has one and it was passed, takes priority over this.
+    pub fn color(mut self, color: ColorChoice) -> Self {
+        self.color = color;
+        self
+    }
+
+    ///
+    /// Turns the result of parsing into the parsed value, or an `Exit` that reports help, the version
+    /// or an error as described by this config. This is what `CLI::try_process_with` does with `std::env::args_os()`.
+    ///
+    /// ```ignore
+    /// let grep = ProcessConfig::sysexits().handle(Grep::parse(&["grep", "--help"]));
+    /// assert_eq!(grep.unwrap_err().code(), 0);
+    /// ```
+    pub fn handle<T>(&self, result: Result<Parse<T>, Error>) -> Result<T, Exit> {
+        match result {
+            Ok(Parse::Success(val)) => Ok(val),
+            Ok(Parse::Help(help)) => Err(self.help(help)),
+            Ok(Parse::Version(version)) => Err(self.version(version)),
+            Err(e) => Err(self.error(e)),
+        }
+    }
+
+    ///
+    /// Use the value of the command's `--color` argument, as found by the parser, instead of the configured color choice.
+    /// Values that can't be parsed are ignored, since parsing them reports the error.
+    pub(crate) fn color_arg(mut self, value: Option<OsString>) -> Self {
+        if let Some(color) = value.as_ref().and_then(|v| v.to_str()?.parse().ok()) {
+            self.color = color;
+        }
+        self
+    }
+    /// How to report a help message.
+    pub(crate) fn help(&self, help: HelpInfo) -> Exit {
+        if help.is_requested() {
+            Exit {
+                report: Report::Help(Box::new(help)),
+                to_stdout: self.help_to_stdout,
+                code: self.help_exit_code,
+                color: self.color,
+            }
+        } else {
+            Exit {
+                report: Report::Help(Box::new(help)),
+                to_stdout: false,
+                code: self.missing_args_exit_code,
+                color: self.color,
+            }
+        }
+    }
+    /// How to report the version of the program.
+    pub(crate) fn version(&self, version: VersionInfo) -> Exit {
+        Exit {
+            report: Report::Version(version),
+            to_stdout: true,
+            code: 0,
+            color: self.color,
+        }
+    }
+    /// How to report the completions of a word, for a completion script.
+    pub(crate) fn completions(&self, values: Vec<String>) -> Exit {
+        Exit {
+            report: Report::Completions(values),
+            to_stdout: true,
+            code: 0,
+            color: self.color,
+        }
+    }
+    /// How to report a parsing error.
+    pub(crate) fn error(&self, e: Error) -> Exit {
+        Exit {
+            report: Report::Error(e),
+            to_stdout: false,
+            code: self.error_exit_code,
+            color: self.color,
+        }
+    }
+}
+
+impl Default for ProcessConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+///
+/// Help info, a version, completions or an error that should be reported before the program exits, from `CLI::try_process`.
+///
+/// This implements `Termination`, so it can be returned from `main`
+/// to print the message and exit with the right code after destructors have run.
+/// Its `Debug` impl shows the message too, but `main` returning `Err(exit)` always exits with code `1`;
+/// use `Outcome` to keep the code.
+pub struct Exit {
+    report: Report,
+    to_stdout: bool,
+    code: u8,
+    color: ColorChoice,
+}
+
+#[derive(Debug)]
+enum Report {
+    // Boxed so that `Result<T, Exit>` stays small.
+    Help(Box<HelpInfo>),
+    Version(VersionInfo),
+    /// Values for a completion script, from `completions::COMPLETE_VAR`.
+    Completions(Vec<String>),
+    Error(Error),
+}
+
+impl Exit {
+    /// The code that the process should exit with.
+    pub fn code(&self) -> u8 {
+        self.code
+    }
+    /// Whether the message is printed to stdout, rather than stderr.
+    pub fn to_stdout(&self) -> bool {
+        self.to_stdout
+    }
+    /// Whether the message is printed with colors, from `ProcessConfig::color` or the command's `--color` argument.
+    pub fn color(&self) -> ColorChoice {
+        self.color
+    }
+    /// The help info to show, if this is help info.
+    pub fn help(&self) -> Option<&HelpInfo> {
+        match &self.report {
+            Report::Help(help) => Some(help),
+            _ => None,
+        }
+    }
+    /// The version to show, if this is a version.
+    pub fn version(&self) -> Option<&VersionInfo> {
+        match &self.report {
+            Report::Version(version) => Some(version),
+            _ => None,
+        }
+    }
+    /// The values to print for a completion script, if the program was run by one.
+    pub fn completions(&self) -> Option<&[String]> {
+        match &self.report {
+            Report::Completions(values) => Some(values),
+            _ => None,
+        }
+    }
+    /// The error to show, if this is an error.
+    pub fn error(&self) -> Option<&Error> {
+        match &self.report {
+            Report::Error(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Print the message to stdout or stderr, as written by `Exit::render`.
+    ///
+    /// If the `color` feature is enabled, the message may be styled with colors, as decided by `ProcessConfig::color`.
+    pub fn print(&self) {
+        // Each value goes on its own line, so there's nothing to print if there are no completions.
+        if self.completions().is_some_and(|values| values.is_empty()) {
+            return;
+        }
+        let message = self.render(self.color.enabled(self.to_stdout));
+        if self.to_stdout {
+            println!("{}", message);
+        } else {
+            eprintln!("{}", message);
+        }
+    }
+    ///
+    /// The message that `Exit::print` prints. Errors start with the name of the program, like `fsctl: error: Unknown flag`.
+    ///
+    /// If `styled` is `true` and the `color` feature is enabled, the message is styled with ANSI colors.
+    /// Styling never changes the text of the message.
+    pub fn render(&self, styled: bool) -> String {
+        match &self.report {
+            Report::Help(help) => HelpRenderer::new().styled(styled).render(help),
+            Report::Version(version) => version.to_string(),
+            Report::Completions(values) => values.join("\n"),
+            Report::Error(e) => {
+                let mut message = String::new();
+                if let Some(program) = e.program() {
+                    message.push_str(program);
+                    message.push_str(": ");
+                }
+                Style::new(styled).error_prefix(&mut message);
+                message.push_str(&e.to_string());
+                message
+            }
+        }
+    }
+    /// Print the message, and exit the process immediately without running destructors.
+    pub fn exit(self) -> ! {
+        self.print();
+        std::process::exit(self.code.into());
+    }
+}
+
+impl Display for Exit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.report {
+            Report::Help(help) => write!(f, "{}", help),
+            Report::Version(version) => write!(f, "{}", version),
+            Report::Completions(values) => write!(f, "{}", values.join("\n")),
+            Report::Error(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// Shows the message, like `Display`, so that an `Exit` returned as the error from `main` isn't shown as a struct.
+impl Debug for Exit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+impl Termination for Exit {
+    fn report(self) -> ExitCode {
+        self.print();
+        ExitCode::from(self.code)
+    }
+}
+
+
+///
+/// `CLI::try_process_with`, given the value of `completions::COMPLETE_VAR`
+/// and the arguments of the program, starting with its name.
+#[doc(hidden)]
+pub fn try_process_from<T: CLI>(
+    config: ProcessConfig,
+    complete_var: Option<OsString>,
+    args: Vec<OsString>,
+) -> Result<T, Exit> {
+    if let Some(values) = completions::requested::<T>(complete_var, &args) {
+        return Err(config.completions(values));
+    }
+    match T::parse_os(args.iter().cloned()) {
+        Ok(Parse::Success(val)) => Ok(val),
+        // Only help and errors are styled, so `--color` is only looked for when they're shown.
+        other => config.color_arg(T::spec().scan_named(args.get(1..).unwrap_or_default(), "--color")).handle(other),
+    }
+}
+
+///
+/// The result of running a program with `CLI::run`, which can be returned from `main`.
+///
+/// ```ignore
+/// fn main() -> type_cli::Outcome {
+///     Grep::run(|Grep(pattern, file)| {
+///         // ...
+///     })
+/// }
+/// ```
+#[derive(Debug)]
+pub enum Outcome<T = ()> {
+    /// The arguments were parsed, and the program ran to completion with this result.
+    Done(T),
+    /// The program exited early to show help info or an error.
+    Exit(Exit),
+}
+
+impl<T> From<Exit> for Outcome<T> {
+    fn from(exit: Exit) -> Self {
+        Outcome::Exit(exit)
+    }
+}
+
+impl<T: Termination> Termination for Outcome<T> {
+    fn report(self) -> ExitCode {
+        match self {
+            Outcome::Done(val) => val.report(),
+            Outcome::Exit(exit) => exit.report(),
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use crate::engine::{self, Layout, Name, Positional};
+use std::ffi::OsString;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+///
+/// A description of a command and its arguments, from `CLI::spec`.
+///
+/// This is everything `#[derive(CLI)]` knows about a command, so completions, documentation
+/// and checks of a command's interface can be built outside of the macro.
+/// Shell completions and `MarkdownReference` are made from this.
+#[derive(Clone, Debug)]
+pub struct CommandSpec {
+    /// The name of the command, which is the name of the program for the top-level command.
+    pub name: &'static str,
+    /// A summary of the command, shown by `-h`.
+    pub about: &'static str,
+    /// The full description of the command, shown by `--help`.
+    pub long_about: &'static str,
+    /// The arguments in the usage line, such as `[OPTIONS] <NAME>`.
+    pub usage: &'static str,
+    /// Whether the command accepts `--version`.
+    pub version: bool,
+    /// The arguments of the command, in the order they were declared: positional arguments, named arguments, then flags.
+    pub args: Vec<ArgSpec>,
+    pub subcommands: Vec<CommandSpec>,
+}
+
+///
+/// A description of an argument of a command, from `CommandSpec::args`.
+#[derive(Clone, Debug)]
+pub struct ArgSpec {
+    pub kind: ArgKind,
+    /// The name of the field, such as `time_out`, or `arg1` for the first field of a tuple struct.
+    pub name: &'static str,
+    /// The long name of a named argument or flag, such as `--time-out`.
+    pub long: Option<&'static str>,
+    /// The short name of a named argument or flag, such as `-t`.
+    pub short: Option<&'static str>,
+    /// A summary of the argument, shown by `-h`.
+    pub help: &'static str,
+    /// The full description of the argument, shown by `--help`.
+    pub long_help: &'static str,
+    /// The type of the field, as written in the source, such as `Option<PathBuf>`.
+    pub ty: &'static str,
+    /// Whether the argument has to be passed. This is `false` for flags and variadic arguments.
+    pub required: bool,
+    pub variadic: bool,
+    ///
+    /// The value of the field when the argument isn't passed, shown with `Debug`.
+    /// This is `None` if the field doesn't hold a value then, like `Option<T>`, or its type doesn't implement `Debug`.
+    pub default: Option<String>,
+    /// The values the argument can take, from `Argument::possible_values`.
+    pub values: Option<&'static [&'static str]>,
+    /// The function from `#[complete_with = "function"]`, which finds values that start with the word under the cursor.
+    pub complete: Option<fn(&str) -> Vec<String>>,
+}
+
+/// How an argument is passed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArgKind {
+    /// An argument passed by its position, such as `FILE` in `rm FILE`.
+    Positional,
+    /// An argument passed by name, followed by its value, such as `--jobs 4`.
+    Named,
+    /// A flag, which doesn't take a value, such as `--force`.
+    Flag,
+}
+
+impl ArgSpec {
+    /// A built-in flag, such as `--help`.
+    fn flag(long: &'static str, short: &'static str, help: &'static str) -> Self {
+        Self {
+            kind: ArgKind::Flag,
+            name: long.trim_start_matches('-'),
+            long: Some(long),
+            short: Some(short),
+            help,
+            long_help: help,
+            ty: "bool",
+            required: false,
+            variadic: false,
+            default: None,
+            values: None,
+            complete: None,
+        }
+    }
+    /// The names this argument can be passed by: the short name, then the long name.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> {
+        self.short.into_iter().chain(self.long)
+    }
+}
+
+impl CommandSpec {
+    /// The named arguments and flags of this command, including `--help` and `--version`.
+    pub fn options(&self) -> Vec<ArgSpec> {
+        let mut options: Vec<ArgSpec> = self
+            .args
+            .iter()
+            .filter(|a| a.kind != ArgKind::Positional)
+            .cloned()
+            .collect();
+        options.push(ArgSpec::flag("--help", "-h", "Print help"));
+        if self.version {
+            options.push(ArgSpec::flag("--version", "-V", "Print version"));
+        }
+        options
+    }
+    /// The positional arguments of this command, in order.
+    pub fn positionals(&self) -> impl Iterator<Item = &ArgSpec> {
+        self.args.iter().filter(|a| a.kind == ArgKind::Positional)
+    }
+    /// The argument with the field name `name`.
+    pub fn arg(&self, name: &str) -> Option<&ArgSpec> {
+        self.args.iter().find(|a| a.name == name)
+    }
+    /// The subcommand called `name`.
+    pub fn subcommand(&self, name: &str) -> Option<&CommandSpec> {
+        self.subcommands.iter().find(|s| s.name == name)
+    }
+    /// The positional arguments, named arguments and flags of this command, for the parser.
+    pub(crate) fn layout(&self) -> (Vec<Positional>, Vec<Name>, Vec<Name>) {
+        let positionals = self
+            .positionals()
+            .map(|a| Positional {
+                required: a.required,
+                variadic: a.variadic,
+            })
+            .collect();
+        let names = |kind| {
+            let args = self.args.iter().filter(move |a: &&ArgSpec| a.kind == kind);
+            args.map(|a| Name {
+                long: a.long.unwrap_or(a.name),
+                short: a.short,
+            })
+            .collect()
+        };
+        (positionals, names(ArgKind::Named), names(ArgKind::Flag))
+    }
+    ///
+    /// The value of the named argument called `long`, such as `--color`, as the parser reads it from `args`,
+    /// which come after the name of the program. Subcommands are followed to the command that's run.
+    pub(crate) fn scan_named(&self, args: &[OsString], long: &str) -> Option<OsString> {
+        if !self.subcommands.is_empty() {
+            let (sub, args) = args.split_first()?;
+            return self.subcommand(&sub.to_string_lossy())?.scan_named(args, long);
+        }
+        let (positionals, named, flags) = self.layout();
+        let layout = Layout {
+            positionals: &positionals,
+            named: &named,
+            flags: &flags,
+            version: self.version,
+        };
+        engine::scan_named(layout, args.iter().cloned(), long)
+    }
+}
+
+///
+/// Describes the default value of an argument, if its type implements `Debug`.
+///
+/// This uses the same autoref specialization as `ArgParser`:
+/// `(&&DefaultText::<T>::new()).describe(default)`.
+pub struct DefaultText<T>(PhantomData<T>);
+
+impl<T> DefaultText<T> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+impl<T> Default for DefaultText<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub trait ViaDebug<T> {
+    fn describe(&self, default: impl FnOnce() -> T) -> Option<String>;
+}
+impl<T: Debug> ViaDebug<T> for &DefaultText<T> {
+    fn describe(&self, default: impl FnOnce() -> T) -> Option<String> {
+        Some(format!("{:?}", default()))
+    }
+}
+
+pub trait ViaNoDebug<T> {
+    fn describe(&self, default: impl FnOnce() -> T) -> Option<String>;
+}
+impl<T> ViaNoDebug<T> for DefaultText<T> {
+    fn describe(&self, _: impl FnOnce() -> T) -> Option<String> {
+        None
+    }
+}
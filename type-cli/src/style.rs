@@ -0,0 +1,155 @@
+use crate::{ArgRef, Argument, Error};
+use std::ffi::OsStr;
+use std::fmt::{self, Display};
+use std::io::IsTerminal;
+use std::str::FromStr;
+
+///
+/// Whether help and errors are styled with ANSI colors when they're printed by `CLI::process`.
+///
+/// Colors are only ever used if the `color` feature is enabled.
+///
+/// `ColorChoice` can be used as an argument, parsed from `auto`, `always` or `never`.
+/// If the command has a `--color` named argument, `CLI::process` uses its value instead of `ProcessConfig::color`,
+/// even if parsing fails, so a program can let the user choose:
+/// ```ignore
+/// #[derive(CLI)]
+/// struct Grep {
+///     pattern: Regex,
+///     #[named]
+///     #[optional]
+///     color: Option<ColorChoice>,
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ColorChoice {
+    ///
+    /// Use colors if the output is a terminal.
+    ///
+    /// Colors are never used if the `NO_COLOR` environment variable is set,
+    /// and are always used if `CLICOLOR_FORCE` is set to anything but `0`.
+    #[default]
+    Auto,
+    /// Always use colors.
+    Always,
+    /// Never use colors.
+    Never,
+}
+
+impl ColorChoice {
+    const NAMES: &'static [&'static str] = &["auto", "always", "never"];
+
+    ///
+    /// Whether to use colors when printing to stdout or stderr.
+    ///
+    /// This is always `false` if the `color` feature isn't enabled.
+    pub fn enabled(self, to_stdout: bool) -> bool {
+        let terminal = if to_stdout {
+            std::io::stdout().is_terminal()
+        } else {
+            std::io::stderr().is_terminal()
+        };
+        let no_color = std::env::var_os("NO_COLOR");
+        let force = std::env::var_os("CLICOLOR_FORCE");
+        self.enabled_in(no_color.as_deref(), force.as_deref(), terminal)
+    }
+    ///
+    /// Whether to use colors, given the values of `NO_COLOR` and `CLICOLOR_FORCE`,
+    /// and whether the output is a terminal.
+    #[doc(hidden)]
+    pub fn enabled_in(self, no_color: Option<&OsStr>, force: Option<&OsStr>, terminal: bool) -> bool {
+        if !cfg!(feature = "color") {
+            return false;
+        }
+        // Empty variables count as unset.
+        let no_color = no_color.is_some_and(|v| !v.is_empty());
+        let force = force.is_some_and(|v| !v.is_empty() && v != "0");
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto if no_color => false,
+            ColorChoice::Auto if force => true,
+            ColorChoice::Auto => terminal,
+        }
+    }
+}
+
+impl Display for ColorChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ColorChoice::Auto => "auto",
+            ColorChoice::Always => "always",
+            ColorChoice::Never => "never",
+        };
+        f.write_str(name)
+    }
+}
+
+/// The error returned when parsing a color choice other than `auto`, `always` or `never`.
+#[derive(Debug, thiserror::Error)]
+#[error("Unknown color choice `{0}`, expected `auto`, `always` or `never`")]
+pub struct UnknownColorChoice(String);
+
+impl FromStr for ColorChoice {
+    type Err = UnknownColorChoice;
+    fn from_str(s: &str) -> Result<Self, UnknownColorChoice> {
+        match s {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(UnknownColorChoice(s.to_string())),
+        }
+    }
+}
+
+impl Argument for ColorChoice {
+    fn parse(val: impl AsRef<str>, arg: ArgRef) -> Result<Self, Error> {
+        let val = val.as_ref();
+        val.parse().map_err(|e| Error::parse(arg, val, e))
+    }
+    fn possible_values() -> Option<&'static [&'static str]> {
+        Some(Self::NAMES)
+    }
+}
+
+///
+/// Adds ANSI styling to text as it's written, if it's enabled.
+///
+/// Text is always written unchanged when styling is disabled.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Style {
+    enabled: bool,
+}
+
+impl Style {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled: enabled && cfg!(feature = "color"),
+        }
+    }
+
+    fn paint(self, code: &str, text: &str, out: &mut String) {
+        if self.enabled {
+            out.push_str("\x1b[");
+            out.push_str(code);
+            out.push('m');
+            out.push_str(text);
+            out.push_str("\x1b[0m");
+        } else {
+            out.push_str(text);
+        }
+    }
+    /// Section headings and labels, such as `ARGUMENTS:` or `Usage:`, in bold.
+    pub fn heading(self, text: &str, out: &mut String) {
+        self.paint("1", text, out);
+    }
+    /// The names of arguments, flags and subcommands, in green.
+    pub fn name(self, text: &str, out: &mut String) {
+        self.paint("32", text, out);
+    }
+    /// `error:` before an error message, in bold red.
+    pub fn error_prefix(self, out: &mut String) {
+        self.paint("1;31", "error:", out);
+        out.push(' ');
+    }
+}
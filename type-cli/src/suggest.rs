@@ -0,0 +1,30 @@
+///
+/// Find the known name that is most likely to be what the user meant to type, if any are close enough.
+///
+/// Leading dashes are ignored when comparing, so `--lime-out` is one edit away from `--time-out`.
+pub(crate) fn suggest(token: &str, known: &[&'static str]) -> Option<&'static str> {
+    let token = token.trim_start_matches('-');
+    known
+        .iter()
+        .map(|&name| (name, distance(token, name.trim_start_matches('-'))))
+        // Only suggest names where most of the characters are right.
+        .filter(|&(name, dist)| dist * 3 <= token.len().max(name.trim_start_matches('-').len()))
+        .min_by_key(|&(_, dist)| dist)
+        .map(|(name, _)| name)
+}
+
+/// The Levenshtein edit distance between two strings.
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diag = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let sub = diag + (ca != cb) as usize;
+            diag = row[j + 1];
+            row[j + 1] = sub.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}